@@ -1,4 +1,14 @@
 use super::*;
 
 #[derive(geng::Assets)]
-pub struct Assets {}
+pub struct Assets {
+    /// DejaVu Sans, see `font-LICENSE.txt`
+    #[asset(path = "font.ttf")]
+    pub font: geng::Font,
+    /// Scenarios of the puzzle mode, a JSON list of [game::model::Scenario]
+    #[asset(path = "puzzles.json")]
+    pub puzzles: String,
+    /// Levels of the campaign, see [menu::Campaign]
+    #[asset(path = "campaign.json")]
+    pub campaign: String,
+}
@@ -0,0 +1,29 @@
+use geng::prelude::*;
+
+use rusty_jam_2::server::Server;
+
+const DEFAULT_ADDR: &str = "127.0.0.1:1155";
+
+fn main() {
+    logger::init().unwrap();
+
+    let args: Vec<String> = std::env::args().collect();
+    let arg_value = |name: &str| {
+        args.iter()
+            .position(|arg| arg == name)
+            .and_then(|index| args.get(index + 1))
+    };
+    let addr = arg_value("--addr")
+        .map(|addr| addr.as_str())
+        .unwrap_or(DEFAULT_ADDR);
+    let spectator_delay = arg_value("--spectator-delay")
+        .map(|delay| {
+            delay
+                .parse::<f64>()
+                .expect("Spectator delay must be a number of seconds")
+        })
+        .unwrap_or(0.0);
+
+    info!("Starting the server on {addr}");
+    Server::new(addr, std::time::Duration::from_secs_f64(spectator_delay)).run();
+}
@@ -0,0 +1,196 @@
+//! Runs matches between AI agents without a window and reports statistics
+
+use geng::prelude::*;
+
+use rusty_jam_2::game::model::logic::agents;
+use rusty_jam_2::game::model::{Rules, Turns};
+use rusty_jam_2::simulation::{agent_seed, play_match, ActionCounts, Outcome};
+
+struct Options {
+    agent_a: String,
+    agent_b: String,
+    matches: u64,
+    seed: u64,
+    max_turns: Turns,
+    rules: Rules,
+    format: Format,
+    output: Option<String>,
+}
+
+#[derive(Clone, Copy)]
+enum Format {
+    Csv,
+    Json,
+}
+
+/// The result of a single match
+#[derive(Debug, Serialize)]
+struct MatchResult {
+    seed: u64,
+    outcome: Outcome,
+    turns: Turns,
+}
+
+#[derive(Debug, Serialize)]
+struct Report {
+    agent_a: String,
+    agent_b: String,
+    matches: u64,
+    win_rate_a: f64,
+    win_rate_b: f64,
+    draw_rate: f64,
+    average_turns: f64,
+    actions_a: ActionCounts,
+    actions_b: ActionCounts,
+    results: Vec<MatchResult>,
+}
+
+fn main() {
+    let options = match Options::parse() {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("{error}");
+            eprintln!(
+                "Usage: rusty-jam-2-sim [--agent-a NAME] [--agent-b NAME] [--matches N] [--seed N] \
+                 [--max-turns N] [--rules FILE] [--format csv|json] [--output FILE]"
+            );
+            eprintln!("Agents: {}", agents::AGENT_NAMES.join(", "));
+            std::process::exit(1);
+        }
+    };
+
+    let report = simulate(&options);
+    let text = match options.format {
+        Format::Json => serde_json::to_string_pretty(&report).expect("Failed to serialize report"),
+        Format::Csv => report.to_csv(),
+    };
+    match &options.output {
+        Some(path) => std::fs::write(path, text).expect("Failed to write the report"),
+        None => println!("{text}"),
+    }
+}
+
+fn simulate(options: &Options) -> Report {
+    let mut results = Vec::new();
+    let mut actions_a = ActionCounts::default();
+    let mut actions_b = ActionCounts::default();
+    for index in 0..options.matches {
+        // Running with the reported seed replays the match
+        let seed = options.seed + index;
+        let mut agent_a = agents::agent_by_name(&options.agent_a, agent_seed(seed, true)).unwrap();
+        let mut agent_b = agents::agent_by_name(&options.agent_b, agent_seed(seed, false)).unwrap();
+        let (outcome, turns) = play_match(
+            &options.rules,
+            options.max_turns,
+            [agent_a.as_mut(), agent_b.as_mut()],
+            [&mut actions_a, &mut actions_b],
+        );
+        results.push(MatchResult {
+            seed,
+            outcome,
+            turns,
+        });
+    }
+
+    let matches = results.len().max(1) as f64;
+    let rate = |outcome: Outcome| {
+        results
+            .iter()
+            .filter(|result| result.outcome == outcome)
+            .count() as f64
+            / matches
+    };
+    Report {
+        agent_a: options.agent_a.clone(),
+        agent_b: options.agent_b.clone(),
+        matches: options.matches,
+        win_rate_a: rate(Outcome::WinA),
+        win_rate_b: rate(Outcome::WinB),
+        draw_rate: rate(Outcome::Draw),
+        average_turns: results
+            .iter()
+            .map(|result| result.turns as f64)
+            .sum::<f64>()
+            / matches,
+        actions_a,
+        actions_b,
+        results,
+    }
+}
+
+impl Report {
+    fn to_csv(&self) -> String {
+        let mut csv = String::from("seed,agent_a,agent_b,outcome,turns\n");
+        for result in &self.results {
+            let outcome = match result.outcome {
+                Outcome::WinA => "win_a",
+                Outcome::WinB => "win_b",
+                Outcome::Draw => "draw",
+            };
+            csv += &format!(
+                "{},{},{},{},{}\n",
+                result.seed, self.agent_a, self.agent_b, outcome, result.turns
+            );
+        }
+        csv += "\nplayer,action,count\n";
+        for (player, counts) in [("a", &self.actions_a), ("b", &self.actions_b)] {
+            for (action, count) in &counts.0 {
+                csv += &format!("{player},{action},{count}\n");
+            }
+        }
+        csv += &format!(
+            "\nwin_rate_a,win_rate_b,draw_rate,average_turns\n{},{},{},{}\n",
+            self.win_rate_a, self.win_rate_b, self.draw_rate, self.average_turns
+        );
+        csv
+    }
+}
+
+impl Options {
+    fn parse() -> Result<Self, String> {
+        let mut options = Self {
+            agent_a: "basic".to_owned(),
+            agent_b: "basic".to_owned(),
+            matches: 1000,
+            seed: 0,
+            max_turns: 200,
+            rules: Rules::default(),
+            format: Format::Json,
+            output: None,
+        };
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or(format!("Missing value for {arg}"));
+            let parse_error = |_| format!("Invalid value for {arg}");
+            match arg.as_str() {
+                "--agent-a" => options.agent_a = value()?,
+                "--agent-b" => options.agent_b = value()?,
+                "--matches" => options.matches = value()?.parse().map_err(parse_error)?,
+                "--seed" => options.seed = value()?.parse().map_err(parse_error)?,
+                "--max-turns" => options.max_turns = value()?.parse().map_err(parse_error)?,
+                "--rules" => {
+                    let path = value()?;
+                    let file = std::fs::read_to_string(&path)
+                        .map_err(|error| format!("Failed to read {path}: {error}"))?;
+                    options.rules = serde_json::from_str(&file)
+                        .map_err(|error| format!("Failed to parse {path}: {error}"))?;
+                }
+                "--format" => {
+                    options.format = match value()?.as_str() {
+                        "csv" => Format::Csv,
+                        "json" => Format::Json,
+                        format => return Err(format!("Unknown format: {format}")),
+                    }
+                }
+                "--output" => options.output = Some(value()?),
+                _ => return Err(format!("Unknown argument: {arg}")),
+            }
+        }
+        for agent in [&options.agent_a, &options.agent_b] {
+            if agents::agent_by_name(agent, 0).is_none() {
+                return Err(format!("Unknown agent: {agent}"));
+            }
+        }
+        Ok(options)
+    }
+}
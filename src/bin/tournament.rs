@@ -0,0 +1,169 @@
+//! Plays every registered agent against every other one on both seats
+//! and ranks them by Elo rating
+
+use rusty_jam_2::game::model::logic::agents;
+use rusty_jam_2::game::model::{Rules, Turns};
+use rusty_jam_2::simulation::{agent_seed, play_match, ActionCounts, Outcome};
+
+const INITIAL_RATING: f64 = 1000.0;
+
+struct Options {
+    matches: u64,
+    seed: u64,
+    max_turns: Turns,
+    k_factor: f64,
+    rules: Rules,
+    output: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+struct Standing {
+    name: String,
+    rating: f64,
+    wins: u64,
+    losses: u64,
+    draws: u64,
+}
+
+fn main() {
+    let options = match Options::parse() {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("{error}");
+            eprintln!(
+                "Usage: rusty-jam-2-tournament [--matches N] [--seed N] [--max-turns N] \
+                 [--k-factor K] [--rules FILE] [--output FILE]"
+            );
+            std::process::exit(1);
+        }
+    };
+
+    let mut standings: Vec<Standing> = agents::AGENT_NAMES
+        .iter()
+        .map(|&name| Standing {
+            name: name.to_owned(),
+            rating: INITIAL_RATING,
+            wins: 0,
+            losses: 0,
+            draws: 0,
+        })
+        .collect();
+
+    let mut seed = options.seed;
+    for a in 0..standings.len() {
+        for b in 0..standings.len() {
+            if a == b {
+                continue;
+            }
+            // Every ordered pair is played, so both agents get each seat
+            for _ in 0..options.matches {
+                let mut agent_a =
+                    agents::agent_by_name(&standings[a].name, agent_seed(seed, true)).unwrap();
+                let mut agent_b =
+                    agents::agent_by_name(&standings[b].name, agent_seed(seed, false)).unwrap();
+                seed += 1;
+                let (outcome, _) = play_match(
+                    &options.rules,
+                    options.max_turns,
+                    [agent_a.as_mut(), agent_b.as_mut()],
+                    [&mut ActionCounts::default(), &mut ActionCounts::default()],
+                );
+                record(&mut standings, a, b, outcome, options.k_factor);
+            }
+        }
+    }
+
+    standings.sort_by(|a, b| b.rating.partial_cmp(&a.rating).unwrap());
+    let report = leaderboard(&standings, &options);
+    match &options.output {
+        Some(path) => std::fs::write(path, report).expect("Failed to write the leaderboard"),
+        None => print!("{report}"),
+    }
+}
+
+/// Updates the standings of the agents with the result of their match
+fn record(standings: &mut [Standing], a: usize, b: usize, outcome: Outcome, k_factor: f64) {
+    let score_a = match outcome {
+        Outcome::WinA => {
+            standings[a].wins += 1;
+            standings[b].losses += 1;
+            1.0
+        }
+        Outcome::WinB => {
+            standings[a].losses += 1;
+            standings[b].wins += 1;
+            0.0
+        }
+        Outcome::Draw => {
+            standings[a].draws += 1;
+            standings[b].draws += 1;
+            0.5
+        }
+    };
+    let expected_a = expected_score(standings[a].rating, standings[b].rating);
+    let delta = k_factor * (score_a - expected_a);
+    standings[a].rating += delta;
+    standings[b].rating -= delta;
+}
+
+/// The probability of the first player winning according to Elo
+fn expected_score(rating: f64, opponent_rating: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf((opponent_rating - rating) / 400.0))
+}
+
+fn leaderboard(standings: &[Standing], options: &Options) -> String {
+    let mut report = format!(
+        "# Leaderboard\n\n{} matches per pairing and seat, seed {}, turn limit {}\n\n",
+        options.matches, options.seed, options.max_turns
+    );
+    report += "| Rank | Agent | Elo | Wins | Losses | Draws |\n";
+    report += "|------|-------|-----|------|--------|-------|\n";
+    for (rank, standing) in standings.iter().enumerate() {
+        report += &format!(
+            "| {} | {} | {:.0} | {} | {} | {} |\n",
+            rank + 1,
+            standing.name,
+            standing.rating,
+            standing.wins,
+            standing.losses,
+            standing.draws
+        );
+    }
+    report
+}
+
+impl Options {
+    fn parse() -> Result<Self, String> {
+        let mut options = Self {
+            matches: 100,
+            seed: 0,
+            max_turns: 200,
+            k_factor: 16.0,
+            rules: Rules::default(),
+            output: None,
+        };
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or(format!("Missing value for {arg}"));
+            let parse_error = || format!("Invalid value for {arg}");
+            match arg.as_str() {
+                "--matches" => options.matches = value()?.parse().ok().ok_or_else(parse_error)?,
+                "--seed" => options.seed = value()?.parse().ok().ok_or_else(parse_error)?,
+                "--max-turns" => {
+                    options.max_turns = value()?.parse().ok().ok_or_else(parse_error)?
+                }
+                "--k-factor" => options.k_factor = value()?.parse().ok().ok_or_else(parse_error)?,
+                "--rules" => {
+                    let path = value()?;
+                    let file = std::fs::read_to_string(&path)
+                        .map_err(|error| format!("Failed to read {path}: {error}"))?;
+                    options.rules = serde_json::from_str(&file)
+                        .map_err(|error| format!("Failed to parse {path}: {error}"))?;
+                }
+                "--output" => options.output = Some(value()?),
+                _ => return Err(format!("Unknown argument: {arg}")),
+            }
+        }
+        Ok(options)
+    }
+}
@@ -0,0 +1,517 @@
+use super::*;
+
+/// Inputs used by the keyboard controls, in the order of priority
+pub const KEYBOARD_ACTIONS: [InputAction; 11] = [
+    InputAction::NextZone,
+    InputAction::Up,
+    InputAction::Down,
+    InputAction::Left,
+    InputAction::Right,
+    InputAction::Confirm,
+    InputAction::Attach,
+    InputAction::Upgrade,
+    InputAction::Move,
+    InputAction::Deactivate,
+    InputAction::Cancel,
+];
+
+/// Zones of the local player that the keyboard selection moves through
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Zone {
+    Buffer,
+    Active,
+    Farm,
+}
+
+/// An alternative to the mouse: shapes are selected and used with the keyboard.
+/// Produces the same actions as the mouse controls.
+#[derive(Debug)]
+pub enum Keyboard {
+    /// The mouse is used
+    Inactive,
+    Browsing {
+        zone: Zone,
+        selected: Option<Id>,
+    },
+    Attaching {
+        triangle: Id,
+        target: Option<Id>,
+        /// Index into the boundary cells of the target
+        slot: usize,
+    },
+    Upgrading {
+        shape: Id,
+        plant: Option<Id>,
+    },
+    Moving {
+        shape: Id,
+        pos: BoardPos,
+    },
+    Targeting {
+        weapon: Id,
+        target: Option<Id>,
+    },
+}
+
+impl Keyboard {
+    pub fn is_inactive(&self) -> bool {
+        matches!(self, Self::Inactive)
+    }
+
+    /// Handles the input if it is used by the keyboard controls in the current state.
+    /// Returns `None` for the inputs left to the mouse controls.
+    pub(super) fn handle_input(
+        &mut self,
+        input: InputAction,
+        ctx: &mut Context,
+    ) -> Option<Vec<PlayerAction>> {
+        let step = match input {
+            InputAction::Left | InputAction::Up => -1,
+            InputAction::Right | InputAction::Down => 1,
+            _ => 0,
+        };
+        let state = std::mem::replace(self, Self::Inactive);
+        let (state, actions) = match state {
+            Self::Inactive => match input {
+                InputAction::NextZone
+                | InputAction::Left
+                | InputAction::Right
+                | InputAction::Up
+                | InputAction::Down => {
+                    let selected = zone_ids(ctx, Zone::Buffer).first().copied();
+                    (
+                        Self::Browsing {
+                            zone: Zone::Buffer,
+                            selected,
+                        },
+                        vec![],
+                    )
+                }
+                _ => return None,
+            },
+            Self::Browsing { zone, selected } => {
+                let ids = zone_ids(ctx, zone);
+                let selected = selected.filter(|id| ids.contains(id));
+                match input {
+                    InputAction::NextZone => {
+                        let zone = match zone {
+                            Zone::Buffer => Zone::Active,
+                            Zone::Active => Zone::Farm,
+                            Zone::Farm => Zone::Buffer,
+                        };
+                        let selected = zone_ids(ctx, zone).first().copied();
+                        (Self::Browsing { zone, selected }, vec![])
+                    }
+                    InputAction::Left
+                    | InputAction::Right
+                    | InputAction::Up
+                    | InputAction::Down => {
+                        let selected = cycle(&ids, selected, step);
+                        (Self::Browsing { zone, selected }, vec![])
+                    }
+                    InputAction::Confirm => match (zone, selected) {
+                        (Zone::Buffer, Some(id)) => (
+                            Self::Browsing {
+                                zone,
+                                selected: None,
+                            },
+                            vec![PlayerAction::ActivateShape(id)],
+                        ),
+                        (Zone::Active, Some(weapon)) => {
+                            let target = target_ids(ctx).first().copied();
+                            (Self::Targeting { weapon, target }, vec![])
+                        }
+                        _ => (Self::Browsing { zone, selected }, vec![]),
+                    },
+                    InputAction::Attach => {
+                        match selected.filter(|&id| own_shape_size(ctx, id) == Some(1)) {
+                            Some(triangle) => {
+                                let target = zone_ids(ctx, Zone::Active)
+                                    .into_iter()
+                                    .find(|&id| id != triangle);
+                                (
+                                    Self::Attaching {
+                                        triangle,
+                                        target,
+                                        slot: 0,
+                                    },
+                                    vec![],
+                                )
+                            }
+                            None => (Self::Browsing { zone, selected }, vec![]),
+                        }
+                    }
+                    InputAction::Upgrade => match selected.filter(|_| zone != Zone::Farm) {
+                        Some(shape) => {
+                            let plant = zone_ids(ctx, Zone::Farm).first().copied();
+                            (Self::Upgrading { shape, plant }, vec![])
+                        }
+                        None => (Self::Browsing { zone, selected }, vec![]),
+                    },
+                    InputAction::Move => {
+                        let moving = selected.and_then(|shape| {
+                            let pos = ctx.player().active_shapes.0.get(&shape)?.board_pos?;
+                            Some(Self::Moving { shape, pos })
+                        });
+                        match moving {
+                            Some(moving) => (moving, vec![]),
+                            None => (Self::Browsing { zone, selected }, vec![]),
+                        }
+                    }
+                    InputAction::Deactivate => match selected.filter(|_| zone == Zone::Active) {
+                        Some(id) => (
+                            Self::Browsing {
+                                zone,
+                                selected: None,
+                            },
+                            vec![PlayerAction::DeactivateShape(id)],
+                        ),
+                        None => (Self::Browsing { zone, selected }, vec![]),
+                    },
+                    InputAction::Cancel => (Self::Inactive, vec![]),
+                    _ => {
+                        *self = Self::Browsing { zone, selected };
+                        return None;
+                    }
+                }
+            }
+            Self::Attaching {
+                triangle,
+                target,
+                slot,
+            } => {
+                let targets: Vec<Id> = zone_ids(ctx, Zone::Active)
+                    .into_iter()
+                    .filter(|&id| id != triangle)
+                    .collect();
+                let target = target.filter(|id| targets.contains(id));
+                let slots = target.map(|id| attach_slots(ctx, id)).unwrap_or_default();
+                let slot = if slots.is_empty() {
+                    0
+                } else {
+                    slot % slots.len()
+                };
+                match input {
+                    InputAction::Up | InputAction::Down => (
+                        Self::Attaching {
+                            triangle,
+                            target: cycle(&targets, target, step),
+                            slot: 0,
+                        },
+                        vec![],
+                    ),
+                    InputAction::Left | InputAction::Right => {
+                        let slot = if slots.is_empty() {
+                            0
+                        } else {
+                            (slot as i64 + step).rem_euclid(slots.len() as i64) as usize
+                        };
+                        (
+                            Self::Attaching {
+                                triangle,
+                                target,
+                                slot,
+                            },
+                            vec![],
+                        )
+                    }
+                    InputAction::Confirm => match (target, slots.get(slot)) {
+                        (Some(target), Some(&pos)) => (
+                            Self::Browsing {
+                                zone: Zone::Active,
+                                selected: Some(target),
+                            },
+                            vec![PlayerAction::AttachShape {
+                                triangle,
+                                target,
+                                pos,
+                            }],
+                        ),
+                        _ => (
+                            Self::Attaching {
+                                triangle,
+                                target,
+                                slot,
+                            },
+                            vec![],
+                        ),
+                    },
+                    InputAction::Cancel => (back_to(ctx, triangle), vec![]),
+                    _ => {
+                        *self = Self::Attaching {
+                            triangle,
+                            target,
+                            slot,
+                        };
+                        return None;
+                    }
+                }
+            }
+            Self::Upgrading { shape, plant } => {
+                let plants = zone_ids(ctx, Zone::Farm);
+                let plant = plant.filter(|id| plants.contains(id));
+                match input {
+                    InputAction::Left
+                    | InputAction::Right
+                    | InputAction::Up
+                    | InputAction::Down => (
+                        Self::Upgrading {
+                            shape,
+                            plant: cycle(&plants, plant, step),
+                        },
+                        vec![],
+                    ),
+                    InputAction::Confirm => match plant {
+                        Some(plant) => (
+                            Self::Browsing {
+                                zone: Zone::Farm,
+                                selected: Some(plant),
+                            },
+                            vec![PlayerAction::UpgradePlant {
+                                source_shape: shape,
+                                target_plant: plant,
+                            }],
+                        ),
+                        None => (Self::Upgrading { shape, plant }, vec![]),
+                    },
+                    InputAction::Cancel => (back_to(ctx, shape), vec![]),
+                    _ => {
+                        *self = Self::Upgrading { shape, plant };
+                        return None;
+                    }
+                }
+            }
+            Self::Moving { shape, pos } => {
+                // Depth is counted from the center line, which is on the right of player A
+                let towards_center = if ctx.player_a() { 1 } else { -1 };
+                let delta = match input {
+                    InputAction::Up => Some((0, 1)),
+                    InputAction::Down => Some((0, -1)),
+                    InputAction::Left => Some((towards_center, 0)),
+                    InputAction::Right => Some((-towards_center, 0)),
+                    _ => None,
+                };
+                match (input, delta) {
+                    (_, Some((depth, row))) => {
+                        let moved = BoardPos {
+                            depth: pos.depth + depth,
+                            row: pos.row + row,
+                        };
+                        let pos = if moved.is_valid() { moved } else { pos };
+                        (Self::Moving { shape, pos }, vec![])
+                    }
+                    (InputAction::Confirm, _) => (
+                        Self::Browsing {
+                            zone: Zone::Active,
+                            selected: Some(shape),
+                        },
+                        vec![PlayerAction::MoveShape { shape, pos }],
+                    ),
+                    (InputAction::Cancel, _) => (back_to(ctx, shape), vec![]),
+                    _ => {
+                        *self = Self::Moving { shape, pos };
+                        return None;
+                    }
+                }
+            }
+            Self::Targeting { weapon, target } => {
+                let targets = target_ids(ctx);
+                let target = target.filter(|id| targets.contains(id));
+                match input {
+                    InputAction::Left
+                    | InputAction::Right
+                    | InputAction::Up
+                    | InputAction::Down => (
+                        Self::Targeting {
+                            weapon,
+                            target: cycle(&targets, target, step),
+                        },
+                        vec![],
+                    ),
+                    InputAction::Confirm => match target {
+                        Some(target) => (
+                            back_to(ctx, weapon),
+                            vec![PlayerAction::Attack { weapon, target }],
+                        ),
+                        None => (Self::Targeting { weapon, target }, vec![]),
+                    },
+                    InputAction::Cancel => (back_to(ctx, weapon), vec![]),
+                    _ => {
+                        *self = Self::Targeting { weapon, target };
+                        return None;
+                    }
+                }
+            }
+        };
+        *self = state;
+        Some(actions)
+    }
+
+    /// Shows the selection to the player
+    pub(super) fn update_render(&self, ctx: &mut Context) {
+        ctx.render.board_cursor = None;
+        ctx.render.attach_ghost = None;
+        ctx.render.attack_preview = None;
+        let bindings = ctx.bindings;
+        let key = |action| bindings.describe(action);
+        let arrows = format!(
+            "{}/{}/{}/{}",
+            key(InputAction::Up),
+            key(InputAction::Down),
+            key(InputAction::Left),
+            key(InputAction::Right)
+        );
+        let (confirm, cancel) = (key(InputAction::Confirm), key(InputAction::Cancel));
+        let (selected, hint) = match *self {
+            Self::Inactive => (None, None),
+            Self::Browsing { selected, .. } => (
+                selected,
+                Some(format!(
+                    "{}: zone, {arrows}: select, {confirm}: use, {}: attach, {}: upgrade, {}: move, {}: deactivate",
+                    key(InputAction::NextZone),
+                    key(InputAction::Attach),
+                    key(InputAction::Upgrade),
+                    key(InputAction::Move),
+                    key(InputAction::Deactivate)
+                )),
+            ),
+            Self::Attaching {
+                triangle,
+                target,
+                slot,
+            } => {
+                let slots = target.map(|id| attach_slots(ctx, id)).unwrap_or_default();
+                ctx.render.attach_ghost =
+                    target
+                        .zip(slots.get(slot).copied())
+                        .map(|(target, pos)| render::AttachGhost {
+                            target,
+                            pos,
+                            valid: true,
+                        });
+                (
+                    Some(triangle),
+                    Some(format!(
+                        "{}/{}: target shape, {}/{}: cell, {confirm}: attach, {cancel}: back",
+                        key(InputAction::Up),
+                        key(InputAction::Down),
+                        key(InputAction::Left),
+                        key(InputAction::Right)
+                    )),
+                )
+            }
+            Self::Upgrading { plant, .. } => (
+                plant,
+                Some(format!(
+                    "{arrows}: choose a plant, {confirm}: upgrade, {cancel}: back"
+                )),
+            ),
+            Self::Moving { shape, pos } => {
+                ctx.render.board_cursor = Some(pos);
+                (
+                    Some(shape),
+                    Some(format!(
+                        "{arrows}: choose a cell, {confirm}: move, {cancel}: back"
+                    )),
+                )
+            }
+            Self::Targeting { weapon, target } => {
+                ctx.render.attack_preview = target.and_then(|target| {
+                    ctx.model
+                        .preview_attack(ctx.player_a(), weapon, target)
+                        .map(|preview| (target, preview, false))
+                });
+                (
+                    Some(weapon),
+                    Some(format!(
+                        "{arrows}: choose a target, {confirm}: attack, {cancel}: back"
+                    )),
+                )
+            }
+        };
+        ctx.render.selected = selected;
+        ctx.render.controller_hint = hint;
+    }
+}
+
+/// Returns to browsing the zone of the shape
+fn back_to(ctx: &Context, id: Id) -> Keyboard {
+    let zone = if ctx.player().active_shapes.0.get(&id).is_some() {
+        Zone::Active
+    } else {
+        Zone::Buffer
+    };
+    Keyboard::Browsing {
+        zone,
+        selected: Some(id),
+    }
+}
+
+/// Ids in the zone of the local player, in a stable order
+fn zone_ids(ctx: &Context, zone: Zone) -> Vec<Id> {
+    let player = ctx.player();
+    let mut ids: Vec<Id> = match zone {
+        Zone::Buffer => player.shape_buffer.0.iter().map(|shape| shape.id).collect(),
+        Zone::Active => player
+            .active_shapes
+            .0
+            .iter()
+            .map(|shape| shape.id)
+            .collect(),
+        Zone::Farm => player
+            .shape_farm
+            .plants
+            .iter()
+            .map(|plant| plant.id)
+            .collect(),
+    };
+    ids.sort();
+    ids
+}
+
+/// Opponent's shapes and plants that can be attacked, in a stable order
+fn target_ids(ctx: &Context) -> Vec<Id> {
+    let opponent = ctx.opponent();
+    let mut ids: Vec<Id> = opponent
+        .active_shapes
+        .0
+        .iter()
+        .map(|shape| shape.id)
+        .chain(opponent.shape_farm.plants.iter().map(|plant| plant.id))
+        .collect();
+    ids.sort();
+    ids
+}
+
+fn own_shape_size(ctx: &Context, id: Id) -> Option<usize> {
+    let player = ctx.player();
+    player
+        .shape_buffer
+        .0
+        .get(&id)
+        .or_else(|| player.active_shapes.0.get(&id))
+        .map(|shape| shape.shape.0.len())
+}
+
+/// Free cells around the shape where a triangle can be attached
+fn attach_slots(ctx: &Context, target: Id) -> Vec<TriPos> {
+    let mut slots: Vec<TriPos> = match ctx.player().active_shapes.0.get(&target) {
+        Some(shape) => shape.shape.boundary().collect(),
+        None => return vec![],
+    };
+    slots.sort_by_key(|pos| (pos.y, pos.x));
+    slots.dedup();
+    slots
+}
+
+/// Moves the selection by `step` in the list, wrapping around
+fn cycle(ids: &[Id], current: Option<Id>, step: i64) -> Option<Id> {
+    if ids.is_empty() {
+        return None;
+    }
+    let index = match current.and_then(|id| ids.iter().position(|&other| other == id)) {
+        Some(index) => (index as i64 + step).rem_euclid(ids.len() as i64) as usize,
+        None => 0,
+    };
+    Some(ids[index])
+}
@@ -1,27 +1,108 @@
 use super::*;
 
+use gesture::{Gesture, GestureRecognizer};
+use input::{Bindings, InputAction};
 use model::*;
 
+mod keyboard;
+mod touch;
+
+use keyboard::Keyboard;
+use touch::hits;
+
 pub struct Controller {
     state: State,
+    keyboard: Keyboard,
+    bindings: Bindings,
+    gestures: GestureRecognizer,
 }
 
 #[derive(Debug)]
 enum State {
     Idle,
-    DraggingShape { shape_id: Id, start_pos: Vec2<f64> },
-    SelectingAttackTarget { weapon_id: Id },
+    DraggingShape {
+        shape_id: Id,
+        /// Where the shape was before the drag, restored on cancel
+        origin: Vec2<R32>,
+        rotation: i64,
+    },
+    SelectingAttackTarget {
+        weapon_id: Id,
+        /// The target clicked once, a second click confirms the attack
+        target: Option<Id>,
+    },
+    DetachingCell {
+        shape_id: Id,
+        pos: TriPos,
+    },
 }
 
 struct Context<'a> {
     model: &'a Model,
     render: &'a mut Render,
+    bindings: &'a Bindings,
     event: geng::Event,
+    /// What the event means for the pointer
+    gesture: Option<Gesture>,
+    cursor: Vec2<f64>,
+    touch_input: bool,
+}
+
+impl<'a> Context<'a> {
+    /// Whether the controls are used by player A
+    fn player_a(&self) -> bool {
+        self.model.perspective()
+    }
+
+    fn player(&self) -> &'a Player {
+        self.model.player(self.player_a())
+    }
+
+    fn opponent(&self) -> &'a Player {
+        self.model.player(!self.player_a())
+    }
+
+    fn layout(&self) -> render::PlayerLayout {
+        self.render.layout.player(self.player_a())
+    }
+
+    /// Whether the event starts the action, touching the screen counts as selecting
+    fn pressed(&self, action: InputAction) -> bool {
+        self.bindings.pressed(action, &self.event)
+            || action == InputAction::Select && matches!(self.event, geng::Event::TouchStart { .. })
+    }
+
+    /// Whether the event releases a key or a button bound to the action
+    fn released(&self, action: InputAction) -> bool {
+        self.bindings.released(action, &self.event)
+    }
+
+    /// Whether the pointer has moved
+    fn moved(&self) -> bool {
+        matches!(
+            self.event,
+            geng::Event::MouseMove { .. } | geng::Event::TouchMove { .. }
+        )
+    }
+
+    /// Extra distance around the shapes that counts as hitting them
+    fn hit_radius(&self) -> R32 {
+        if self.touch_input {
+            r32(touch::TOUCH_HIT_RADIUS)
+        } else {
+            R32::ZERO
+        }
+    }
 }
 
 impl Controller {
     pub fn new() -> Self {
-        Self { state: State::Idle }
+        Self {
+            state: State::Idle,
+            keyboard: Keyboard::Inactive,
+            bindings: Bindings::load(),
+            gestures: GestureRecognizer::new(),
+        }
     }
 
     pub fn handle_event(
@@ -30,58 +111,203 @@ impl Controller {
         render: &mut Render,
         event: geng::Event,
     ) -> Vec<PlayerAction> {
-        let context = Context {
+        let gesture = self.gestures.handle_event(&self.bindings, &event);
+        let mut context = Context {
             model,
-            render,
+            render: &mut *render,
+            bindings: &self.bindings,
             event,
+            gesture,
+            cursor: self.gestures.cursor(),
+            touch_input: self.gestures.is_touch(),
         };
+        if let State::Idle = self.state {
+            match context.event {
+                geng::Event::KeyDown { key } => {
+                    let input = self.bindings.key_action(key, &keyboard::KEYBOARD_ACTIONS);
+                    let actions =
+                        input.and_then(|input| self.keyboard.handle_input(input, &mut context));
+                    if let Some(actions) = actions {
+                        self.keyboard.update_render(&mut context);
+                        return actions;
+                    }
+                }
+                geng::Event::MouseDown { .. } | geng::Event::TouchStart { .. }
+                    if !self.keyboard.is_inactive() =>
+                {
+                    // Using the mouse drops the keyboard selection
+                    self.keyboard = Keyboard::Inactive;
+                    self.keyboard.update_render(&mut context);
+                }
+                _ => {}
+            }
+        }
         let state = std::mem::replace(&mut self.state, State::Idle);
         let (new_state, actions) = state.handle_event(context);
         self.state = new_state;
+        if self.keyboard.is_inactive() {
+            self.update_render(render);
+        }
         actions
     }
+
+    /// Turns a long press on a shape into choosing it as a weapon
+    pub fn update(&mut self, delta_time: f64, render: &mut Render) {
+        if !matches!(
+            self.gestures.update(delta_time),
+            Some(Gesture::LongPress(_))
+        ) {
+            return;
+        }
+        if let State::DraggingShape { shape_id, .. } = self.state {
+            std::mem::replace(&mut self.state, State::Idle).cancel(render);
+            self.state = State::SelectingAttackTarget {
+                weapon_id: shape_id,
+                target: None,
+            };
+            self.update_render(render);
+        }
+    }
+
+    fn update_render(&self, render: &mut Render) {
+        render.controller_hint = self.state.hint(&self.bindings);
+        render.selected = match self.state {
+            State::SelectingAttackTarget { weapon_id, .. } => Some(weapon_id),
+            State::DetachingCell { shape_id, .. } => Some(shape_id),
+            _ => None,
+        };
+    }
+
+    /// Whether the event asks to take back the last action, only outside of interactions.
+    /// Browsing with the keyboard is not an interaction, so that keyboard players can undo too.
+    pub fn undo_pressed(&self, event: &geng::Event) -> bool {
+        let keyboard_idle = matches!(
+            self.keyboard,
+            Keyboard::Inactive | Keyboard::Browsing { .. }
+        );
+        matches!(self.state, State::Idle)
+            && keyboard_idle
+            && self.bindings.pressed(InputAction::Undo, event)
+    }
+
+    /// Whether no interaction is in progress
+    pub fn is_idle(&self) -> bool {
+        matches!(self.state, State::Idle) && self.keyboard.is_inactive()
+    }
 }
 
 impl State {
     pub fn handle_event<'a>(self, context: Context<'a>) -> (Self, Vec<PlayerAction>) {
+        let cancel = context.pressed(InputAction::Cancel);
+        if cancel && !matches!(self, Self::Idle) {
+            self.cancel(context.render);
+            return (Self::Idle, vec![]);
+        }
         match self {
             Self::Idle => handle_idle(context),
             Self::DraggingShape {
                 shape_id,
-                start_pos,
-            } => handle_drag_shape(shape_id, start_pos, context),
-            Self::SelectingAttackTarget { weapon_id } => {
-                handle_select_attack_target(weapon_id, context)
+                origin,
+                rotation,
+            } => handle_drag_shape(shape_id, origin, rotation, context),
+            Self::SelectingAttackTarget { weapon_id, target } => {
+                handle_select_attack_target(weapon_id, target, context)
             }
+            Self::DetachingCell { shape_id, pos } => handle_detach_cell(shape_id, pos, context),
+        }
+    }
+
+    /// Abandons the interaction, leaving everything as it was before it started
+    fn cancel(self, render: &mut Render) {
+        match self {
+            Self::Idle => {}
+            Self::DetachingCell { .. } => render.detached_cell = None,
+            Self::DraggingShape {
+                shape_id, origin, ..
+            } => {
+                render.positions.insert(shape_id, origin);
+                render.dragging = None;
+                render.drag_rotation = 0;
+                render.attach_ghost = None;
+            }
+            Self::SelectingAttackTarget { .. } => render.attack_preview = None,
+        }
+    }
+
+    /// Describes the current interaction to the player
+    fn hint(&self, bindings: &Bindings) -> Option<String> {
+        let cancel = bindings.describe(InputAction::Cancel);
+        match self {
+            Self::Idle => None,
+            Self::DraggingShape { .. } => Some(format!(
+                "Drag to move, hold in place to attack: {} to rotate, {cancel} to cancel",
+                bindings.describe(InputAction::Rotate)
+            )),
+            Self::SelectingAttackTarget { .. } => {
+                Some(format!("Choose a target: {cancel} to cancel"))
+            }
+            Self::DetachingCell { .. } => Some(format!(
+                "Drag the cell out of the shape: {cancel} to cancel"
+            )),
         }
     }
 }
 
 fn handle_idle<'a>(ctx: Context<'a>) -> (State, Vec<PlayerAction>) {
     match ctx.event {
-        geng::Event::KeyDown { key } => match key {
-            geng::Key::Enter => (State::Idle, vec![PlayerAction::EndTurn]),
-            _ => (State::Idle, vec![]),
-        },
-        geng::Event::MouseDown {
-            position,
-            button: geng::MouseButton::Left,
-        } => {
+        _ if ctx.pressed(InputAction::EndTurn) => (State::Idle, vec![PlayerAction::EndTurn]),
+        _ if ctx.pressed(InputAction::Select) => {
+            let position = ctx.cursor;
             let mouse_world_pos = ctx.render.screen_to_world(position);
+            if ctx
+                .render
+                .layout
+                .end_turn_button
+                .contains(mouse_world_pos.map(|x| x.as_f32()))
+            {
+                return (State::Idle, vec![PlayerAction::EndTurn]);
+            }
             for shape in ctx
-                .model
-                .player_a
+                .player()
                 .shape_buffer
                 .0
                 .iter()
-                .chain(&ctx.model.player_a.active_shapes.0)
+                .chain(&ctx.player().active_shapes.0)
             {
                 if let Some(&shape_pos) = ctx.render.positions.get(shape.id) {
-                    if shape.shape.contains(mouse_world_pos - shape_pos) {
+                    if hits(&shape.shape, mouse_world_pos - shape_pos, ctx.hit_radius()) {
+                        // The shape only follows the pointer once the drag starts
+                        ctx.render.drag_rotation = 0;
                         return (
                             State::DraggingShape {
                                 shape_id: shape.id,
-                                start_pos: position,
+                                origin: shape_pos,
+                                rotation: 0,
+                            },
+                            vec![],
+                        );
+                    }
+                }
+            }
+            (State::Idle, vec![])
+        }
+        _ if ctx.pressed(InputAction::Detach) => {
+            let mouse_world_pos = ctx.render.screen_to_world(ctx.cursor);
+            for shape in ctx
+                .player()
+                .shape_buffer
+                .0
+                .iter()
+                .chain(&ctx.player().active_shapes.0)
+                .filter(|shape| shape.shape.0.len() > 1)
+            {
+                if let Some(&shape_pos) = ctx.render.positions.get(shape.id) {
+                    if let Some(pos) = shape.shape.cell_at(mouse_world_pos - shape_pos) {
+                        ctx.render.detached_cell = Some((pos, mouse_world_pos));
+                        return (
+                            State::DetachingCell {
+                                shape_id: shape.id,
+                                pos,
                             },
                             vec![],
                         );
@@ -94,178 +320,334 @@ fn handle_idle<'a>(ctx: Context<'a>) -> (State, Vec<PlayerAction>) {
     }
 }
 
-fn handle_drag_shape<'a>(
+fn handle_detach_cell<'a>(
     shape_id: Id,
-    start_pos: Vec2<f64>,
+    pos: TriPos,
     ctx: Context<'a>,
 ) -> (State, Vec<PlayerAction>) {
     match ctx.event {
-        geng::Event::MouseUp {
-            button: geng::MouseButton::Left,
-            position,
-        } => {
-            if start_pos == position {
-                (
-                    State::SelectingAttackTarget {
-                        weapon_id: shape_id,
-                    },
-                    vec![],
-                )
+        _ if ctx.released(InputAction::Detach) => {
+            // Detach only if the cell has been dragged out of the shape
+            let mouse_world_pos = ctx.render.screen_to_world(ctx.cursor);
+            let dragged_out = ctx
+                .player()
+                .shape_buffer
+                .0
+                .get(&shape_id)
+                .or_else(|| ctx.player().active_shapes.0.get(&shape_id))
+                .and_then(|shape| {
+                    ctx.render
+                        .positions
+                        .get(shape_id)
+                        .map(|&shape_pos| !shape.shape.contains(mouse_world_pos - shape_pos))
+                })
+                .unwrap_or(false);
+            ctx.render.detached_cell = None;
+            let actions = if dragged_out {
+                vec![PlayerAction::DetachCell {
+                    shape: shape_id,
+                    pos,
+                }]
             } else {
-                (State::Idle, vec![])
-            }
+                vec![]
+            };
+            (State::Idle, actions)
         }
-        geng::Event::MouseMove { position, .. } => {
+        _ if ctx.moved() => {
+            ctx.render.detached_cell = Some((pos, ctx.render.screen_to_world(ctx.cursor)));
+            (State::DetachingCell { shape_id, pos }, vec![])
+        }
+        _ => (State::DetachingCell { shape_id, pos }, vec![]),
+    }
+}
+
+fn handle_drag_shape<'a>(
+    shape_id: Id,
+    origin: Vec2<R32>,
+    rotation: i64,
+    ctx: Context<'a>,
+) -> (State, Vec<PlayerAction>) {
+    let dragging = |rotation| State::DraggingShape {
+        shape_id,
+        origin,
+        rotation,
+    };
+    match ctx.gesture {
+        _ if ctx.pressed(InputAction::Rotate) => {
+            let rotation = (rotation + 1) % 6;
+            ctx.render.drag_rotation = rotation;
+            (dragging(rotation), vec![])
+        }
+        // On touch screens a long press chooses the weapon instead
+        Some(Gesture::Click(_)) if !ctx.touch_input => (
+            State::SelectingAttackTarget {
+                weapon_id: shape_id,
+                target: None,
+            },
+            vec![],
+        ),
+        // The shape was not dragged anywhere
+        Some(Gesture::Click(_) | Gesture::Release(_)) => (State::Idle, vec![]),
+        Some(Gesture::Drop(position)) => {
+            ctx.render.dragging = None;
+            let ghost = ctx.render.attach_ghost.take();
+            if let Some(ghost) = ghost {
+                let actions = if ghost.valid {
+                    vec![PlayerAction::AttachShape {
+                        triangle: shape_id,
+                        target: ghost.target,
+                        pos: ghost.pos,
+                    }]
+                } else {
+                    ctx.render.positions.insert(shape_id, origin);
+                    vec![]
+                };
+                return (State::Idle, actions);
+            }
+            let shape = match ctx
+                .player()
+                .shape_buffer
+                .0
+                .get(&shape_id)
+                .or_else(|| ctx.player().active_shapes.0.get(&shape_id))
+            {
+                Some(shape) => shape,
+                None => return (State::Idle, vec![]),
+            };
+
+            if let Some(action) = find_merge(&ctx, shape, rotation) {
+                return (State::Idle, vec![action]);
+            }
+
+            // Move the shape to the zone it was dropped in
             let mouse_world_pos = ctx.render.screen_to_world(position);
-            // Move the shape
-            let bounds = ctx
-                .render
-                .layout
-                .shape_buffer_a
-                .join(&ctx.render.layout.active_shapes_a)
-                .join(&ctx.render.layout.shape_farm_a);
-            let pos = bounds.clamp_point(mouse_world_pos.map(|x| x.as_f32()));
-            let actions = {
-                if ctx.render.layout.shape_buffer_a.contains(pos) {
+            let pos = mouse_world_pos.map(|x| x.as_f32());
+            let layout = ctx.layout();
+            let is_active = shape.board_pos.is_some();
+            let actions = if layout.shape_buffer.contains(pos) {
+                if is_active {
                     vec![PlayerAction::DeactivateShape(shape_id)]
-                } else if ctx.render.layout.active_shapes_a.contains(pos) {
-                    vec![PlayerAction::ActivateShape(shape_id)]
-                } else if ctx.render.layout.shape_farm_a.contains(pos) {
-                    let mut upgradable_plants = ctx
-                        .model
-                        .player_a
-                        .shape_farm
-                        .plants
-                        .iter()
-                        .filter_map(|plant| {
-                            ctx.render.positions.get(plant.id).and_then(|pos| {
-                                ctx.render
-                                    .scales
-                                    .get(plant.id)
-                                    .map(|scale| (pos, scale, plant))
-                            })
-                        })
-                        .filter(|(pos, scale, plant)| {
-                            plant.shape.contains((mouse_world_pos - **pos) / **scale)
-                        })
-                        .map(|(_, _, plant)| plant.id);
-                    upgradable_plants
-                        .next()
-                        .map(|plant_id| {
-                            vec![PlayerAction::UpgradePlant {
-                                source_shape: shape_id,
-                                target_plant: plant_id,
+                } else {
+                    vec![]
+                }
+            } else if layout.active_shapes.contains(pos) {
+                if is_active {
+                    ctx.render
+                        .layout
+                        .board_pos_at(ctx.player_a(), pos)
+                        .map(|pos| {
+                            vec![PlayerAction::MoveShape {
+                                shape: shape_id,
+                                pos,
                             }]
                         })
-                        .unwrap_or(vec![])
+                        .unwrap_or_default()
                 } else {
-                    vec![]
+                    vec![PlayerAction::ActivateShape(shape_id)]
                 }
+            } else if layout.shape_farm.contains(pos) {
+                ctx.player()
+                    .shape_farm
+                    .plants
+                    .iter()
+                    .find(|plant| {
+                        match (
+                            ctx.render.positions.get(plant.id),
+                            ctx.render.scales.get(plant.id),
+                        ) {
+                            (Some(&plant_pos), Some(&scale)) => hits(
+                                &plant.shape,
+                                (mouse_world_pos - plant_pos) / scale,
+                                ctx.hit_radius() / scale,
+                            ),
+                            _ => false,
+                        }
+                    })
+                    .map(|plant| {
+                        vec![PlayerAction::UpgradePlant {
+                            source_shape: shape_id,
+                            target_plant: plant.id,
+                        }]
+                    })
+                    .unwrap_or_default()
+            } else {
+                vec![]
             };
-            let pos = pos.map(r32);
+            if layout.shape_buffer.contains(pos) {
+                ctx.render.pinned.insert(shape_id);
+            } else {
+                // Active shapes snap to the board and others are consumed,
+                // so the shape only stays where it was dropped inside the buffer
+                ctx.render.positions.insert(shape_id, origin);
+            }
+            (State::Idle, actions)
+        }
+        Some(Gesture::DragStart(position) | Gesture::Drag(position)) => {
+            ctx.render.dragging = Some(shape_id);
+            let mouse_world_pos = ctx.render.screen_to_world(position);
+            let layout = ctx.layout();
+            let bounds = layout
+                .shape_buffer
+                .join(&layout.active_shapes)
+                .join(&layout.shape_farm);
+            let pos = bounds
+                .clamp_point(mouse_world_pos.map(|x| x.as_f32()))
+                .map(r32);
 
-            let mut attachments = ctx
-                .model
-                .player_a
-                .active_shapes
+            // Single triangles show where they would be attached on release
+            let is_triangle = ctx
+                .player()
+                .shape_buffer
                 .0
-                .iter()
-                .filter(|shape| shape.id != shape_id)
-                .filter_map(|shape| {
-                    ctx.render
-                        .positions
-                        .get(shape.id)
-                        .and_then(|&shape_pos| try_attach(pos, &shape.shape, shape_pos))
-                        .map(|pos| (shape.id, pos))
-                });
-            if let Some((target_id, attach_pos)) = attachments.next() {
-                return (
-                    State::DraggingShape {
-                        shape_id,
-                        start_pos,
-                    },
-                    vec![PlayerAction::AttachShape {
-                        triangle: shape_id,
-                        target: target_id,
-                        pos: attach_pos,
-                    }],
-                );
-            }
+                .get(&shape_id)
+                .or_else(|| ctx.player().active_shapes.0.get(&shape_id))
+                .map_or(false, |shape| shape.shape.0.len() == 1);
+            ctx.render.attach_ghost = if is_triangle {
+                ctx.player()
+                    .active_shapes
+                    .0
+                    .iter()
+                    .filter(|shape| shape.id != shape_id)
+                    .find_map(|shape| {
+                        let shape_pos = *ctx.render.positions.get(shape.id)?;
+                        let (pos, valid) = match try_attach(pos, &shape.shape, shape_pos) {
+                            Some(pos) => (pos, true),
+                            None => (shape.shape.cell_at(pos - shape_pos)?, false),
+                        };
+                        Some(render::AttachGhost {
+                            target: shape.id,
+                            pos,
+                            valid,
+                        })
+                    })
+            } else {
+                None
+            };
 
-            let current_pos = match ctx.render.positions.get_mut(shape_id) {
-                Some(pos) => pos,
+            match ctx.render.positions.get_mut(shape_id) {
+                Some(current_pos) => {
+                    *current_pos = pos;
+                    (dragging(rotation), vec![])
+                }
                 None => {
-                    return (State::Idle, vec![]);
+                    ctx.render.dragging = None;
+                    (State::Idle, vec![])
                 }
-            };
-            *current_pos = pos;
-            (
-                State::DraggingShape {
-                    shape_id,
-                    start_pos,
-                },
-                actions,
-            )
+            }
         }
-        _ => (
-            State::DraggingShape {
-                shape_id,
-                start_pos,
-            },
-            vec![],
-        ),
+        _ => (dragging(rotation), vec![]),
     }
 }
 
-fn handle_select_attack_target<'a>(weapon_id: Id, ctx: Context<'a>) -> (State, Vec<PlayerAction>) {
+fn handle_select_attack_target<'a>(
+    weapon_id: Id,
+    target: Option<Id>,
+    ctx: Context<'a>,
+) -> (State, Vec<PlayerAction>) {
     match ctx.event {
-        geng::Event::MouseDown {
-            position,
-            button: geng::MouseButton::Left,
-        } => {
-            let mouse_world_pos = ctx.render.screen_to_world(position);
-            for (id, scale, pos, shape) in ctx
-                .model
-                .player_b
-                .active_shapes
-                .0
-                .iter()
-                .filter_map(|shape| {
-                    ctx.render
-                        .positions
-                        .get(shape.id)
-                        .map(|pos| (shape.id, r32(1.0), *pos, &shape.shape))
-                })
-                .chain(
-                    ctx.model
-                        .player_b
-                        .shape_farm
-                        .plants
-                        .iter()
-                        .filter_map(|plant| {
-                            ctx.render.positions.get(plant.id).and_then(|pos| {
-                                ctx.render
-                                    .scales
-                                    .get(plant.id)
-                                    .map(|scale| (plant.id, *scale, *pos, &plant.shape))
-                            })
-                        }),
-                )
-            {
-                if shape.contains((mouse_world_pos - pos) / scale) {
-                    return (
+        _ if ctx.moved() => {
+            let position = ctx.cursor;
+            // Show what would happen when attacking the hovered target
+            let hovered = target.or_else(|| attack_target_at(&ctx, position));
+            ctx.render.attack_preview = hovered.and_then(|target_id| {
+                ctx.model
+                    .preview_attack(ctx.player_a(), weapon_id, target_id)
+                    .map(|preview| (target_id, preview, target.is_some()))
+            });
+            (State::SelectingAttackTarget { weapon_id, target }, vec![])
+        }
+        _ if ctx.pressed(InputAction::Select) => {
+            let clicked = attack_target_at(&ctx, ctx.cursor);
+            match (clicked, target) {
+                (Some(clicked), Some(target)) if clicked == target => {
+                    ctx.render.attack_preview = None;
+                    (
                         State::Idle,
                         vec![PlayerAction::Attack {
                             weapon: weapon_id,
-                            target: id,
+                            target,
                         }],
-                    );
+                    )
+                }
+                (Some(clicked), _) => {
+                    match ctx.model.preview_attack(ctx.player_a(), weapon_id, clicked) {
+                        Some(preview) => {
+                            ctx.render.attack_preview = Some((clicked, preview, true));
+                            (
+                                State::SelectingAttackTarget {
+                                    weapon_id,
+                                    target: Some(clicked),
+                                },
+                                vec![],
+                            )
+                        }
+                        None => {
+                            ctx.render.attack_preview = None;
+                            (State::Idle, vec![])
+                        }
+                    }
+                }
+                (None, _) => {
+                    ctx.render.attack_preview = None;
+                    (State::Idle, vec![])
                 }
             }
-            (State::Idle, vec![])
         }
-        _ => (State::SelectingAttackTarget { weapon_id }, vec![]),
+        _ => (State::SelectingAttackTarget { weapon_id, target }, vec![]),
+    }
+}
+
+/// Finds the opponent's shape or plant under the cursor
+fn attack_target_at(ctx: &Context, position: Vec2<f64>) -> Option<Id> {
+    let mouse_world_pos = ctx.render.screen_to_world(position);
+    let active = ctx.opponent().active_shapes.0.iter().filter_map(|shape| {
+        ctx.render
+            .positions
+            .get(shape.id)
+            .map(|pos| (shape.id, r32(1.0), *pos, &shape.shape))
+    });
+    let plants = ctx.opponent().shape_farm.plants.iter().filter_map(|plant| {
+        ctx.render.positions.get(plant.id).and_then(|pos| {
+            ctx.render
+                .scales
+                .get(plant.id)
+                .map(|scale| (plant.id, *scale, *pos, &plant.shape))
+        })
+    });
+    active
+        .chain(plants)
+        .find(|(_, scale, pos, shape)| {
+            hits(
+                shape,
+                (mouse_world_pos - *pos) / *scale,
+                ctx.hit_radius() / *scale,
+            )
+        })
+        .map(|(id, ..)| id)
+}
+
+/// Finds a shape the dragged multi-cell shape snaps to
+fn find_merge(ctx: &Context, shape: &AliveShape, rotation: i64) -> Option<PlayerAction> {
+    if shape.shape.0.len() <= 1 {
+        return None;
     }
+    let shape_pos = *ctx.render.positions.get(shape.id)?;
+    ctx.player()
+        .active_shapes
+        .0
+        .iter()
+        .filter(|target| target.id != shape.id)
+        .find_map(|target| {
+            let target_pos = *ctx.render.positions.get(target.id)?;
+            try_merge(&shape.shape, shape_pos, rotation, &target.shape, target_pos).map(|offset| {
+                PlayerAction::MergeShapes {
+                    source: shape.id,
+                    target: target.id,
+                    rotation,
+                    offset,
+                }
+            })
+        })
 }
 
 fn try_attach(center: Vec2<R32>, shape: &Shape, shape_pos: Vec2<R32>) -> Option<TriPos> {
@@ -273,3 +655,21 @@ fn try_attach(center: Vec2<R32>, shape: &Shape, shape_pos: Vec2<R32>) -> Option<
         .boundary()
         .find(|pos| logic::inside_triangle(center, pos.to_vertices().map(|pos| pos + shape_pos)))
 }
+
+/// Finds the offset at which the source shape snaps to the target shape
+fn try_merge(
+    source: &Shape,
+    source_pos: Vec2<R32>,
+    rotation: i64,
+    target: &Shape,
+    target_pos: Vec2<R32>,
+) -> Option<TriPos> {
+    let rotated = source.transformed(rotation, TriPos { x: 0, y: 0 });
+    let first = *rotated.0.first()?;
+    let cell = TriPos::from_cartesian(source_pos - target_pos + first.to_cartesian());
+    let offset = TriPos {
+        x: cell.x - first.x,
+        y: cell.y - first.y,
+    };
+    target.merge(source, rotation, offset).map(|_| offset)
+}
@@ -0,0 +1,13 @@
+use super::*;
+
+/// Distance around the shapes that still counts as touching them, fingers are not precise
+pub const TOUCH_HIT_RADIUS: f32 = 0.5;
+
+/// Checks whether the point hits the shape, or comes within `radius` of any of its cells
+pub fn hits(shape: &Shape, point: Vec2<R32>, radius: R32) -> bool {
+    shape.contains(point)
+        || shape
+            .0
+            .iter()
+            .any(|cell| (cell.to_cartesian() - point).len() <= radius)
+}
@@ -0,0 +1,145 @@
+use super::*;
+
+use input::{Bindings, InputAction};
+
+/// Distance in pixels the mouse can move between press and release of a click
+pub const CLICK_DISTANCE: f64 = 4.0;
+/// Distance in pixels a finger can move while still counting as staying in place
+pub const TOUCH_CLICK_DISTANCE: f64 = 10.0;
+/// Longest press in seconds that still counts as a click
+pub const CLICK_TIME: f64 = 0.3;
+/// Seconds the pointer has to be held in place for the attack gesture
+pub const LONG_PRESS_TIME: f64 = 0.5;
+
+/// What the player did with the pointer, recognized from the raw input events
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Gesture {
+    /// The pointer was pressed
+    Press(Vec2<f64>),
+    /// The pointer moved far enough from where it was pressed to start dragging
+    DragStart(Vec2<f64>),
+    /// The pointer moved while dragging
+    Drag(Vec2<f64>),
+    /// The pointer was released after dragging
+    Drop(Vec2<f64>),
+    /// The pointer was pressed and released quickly without moving
+    Click(Vec2<f64>),
+    /// The pointer was held in place, used to choose a weapon
+    LongPress(Vec2<f64>),
+    /// The pointer was released after a press that was neither a click nor a drag
+    Release(Vec2<f64>),
+}
+
+#[derive(Debug)]
+struct Press {
+    start: Vec2<f64>,
+    /// Seconds since the press
+    held: f64,
+    touch: bool,
+    dragging: bool,
+    long_pressed: bool,
+}
+
+impl Press {
+    fn click_distance(&self) -> f64 {
+        if self.touch {
+            TOUCH_CLICK_DISTANCE
+        } else {
+            CLICK_DISTANCE
+        }
+    }
+}
+
+/// Turns the mouse and touch events into [Gesture]s.
+/// Pressing anything bound to [InputAction::Select] or touching the screen starts a press.
+#[derive(Debug, Default)]
+pub struct GestureRecognizer {
+    press: Option<Press>,
+    /// Last known position of the pointer, so that mouse actions can be bound to keys
+    cursor: Vec2<f64>,
+    /// Whether the last pointer input came from a touch screen
+    touch: bool,
+}
+
+impl GestureRecognizer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cursor(&self) -> Vec2<f64> {
+        self.cursor
+    }
+
+    /// Whether the last pointer input came from a touch screen
+    pub fn is_touch(&self) -> bool {
+        self.touch
+    }
+
+    pub fn handle_event(&mut self, bindings: &Bindings, event: &geng::Event) -> Option<Gesture> {
+        match event {
+            geng::Event::MouseMove { position, .. }
+            | geng::Event::MouseDown { position, .. }
+            | geng::Event::MouseUp { position, .. } => {
+                self.cursor = *position;
+                self.touch = false;
+            }
+            geng::Event::TouchStart { touches }
+            | geng::Event::TouchMove { touches }
+            | geng::Event::TouchEnd { touches } => {
+                if let Some(point) = touches.first() {
+                    self.cursor = point.position;
+                }
+                self.touch = true;
+            }
+            _ => {}
+        }
+        let position = self.cursor;
+        if bindings.pressed(InputAction::Select, event)
+            || matches!(event, geng::Event::TouchStart { .. })
+        {
+            self.press = Some(Press {
+                start: position,
+                held: 0.0,
+                touch: self.touch,
+                dragging: false,
+                long_pressed: false,
+            });
+            return Some(Gesture::Press(position));
+        }
+        if bindings.released(InputAction::Select, event)
+            || matches!(event, geng::Event::TouchEnd { .. })
+        {
+            let press = self.press.take()?;
+            let gesture = if press.dragging {
+                Gesture::Drop(position)
+            } else if press.long_pressed || press.held > CLICK_TIME {
+                Gesture::Release(position)
+            } else {
+                Gesture::Click(press.start)
+            };
+            return Some(gesture);
+        }
+        if let geng::Event::MouseMove { .. } | geng::Event::TouchMove { .. } = event {
+            let press = self.press.as_mut()?;
+            if press.dragging {
+                return Some(Gesture::Drag(position));
+            }
+            if !press.long_pressed && (position - press.start).len() > press.click_distance() {
+                press.dragging = true;
+                return Some(Gesture::DragStart(position));
+            }
+        }
+        None
+    }
+
+    /// Returns the long press once the pointer has been held in place for long enough
+    pub fn update(&mut self, delta_time: f64) -> Option<Gesture> {
+        let press = self.press.as_mut()?;
+        press.held += delta_time;
+        if press.dragging || press.long_pressed || press.held < LONG_PRESS_TIME {
+            return None;
+        }
+        press.long_pressed = true;
+        Some(Gesture::LongPress(press.start))
+    }
+}
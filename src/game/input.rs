@@ -0,0 +1,218 @@
+use super::*;
+
+/// Where the bindings are saved between runs
+const BINDINGS_PATH: &str = "bindings.json";
+
+/// Something the player can do with a key or a mouse button
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum InputAction {
+    EndTurn,
+    /// Grab a shape or choose a target
+    Select,
+    /// Drag a cell out of a shape
+    Detach,
+    /// Abandon the current interaction
+    Cancel,
+    /// Rotate the dragged shape
+    Rotate,
+    /// Take back the last action of the turn
+    Undo,
+    Pause,
+    /// Keyboard controls: switch between the buffer, the active zone and the farm
+    NextZone,
+    Up,
+    Down,
+    Left,
+    Right,
+    Confirm,
+    Attach,
+    Upgrade,
+    Move,
+    Deactivate,
+}
+
+pub const INPUT_ACTIONS: [InputAction; 17] = [
+    InputAction::EndTurn,
+    InputAction::Select,
+    InputAction::Detach,
+    InputAction::Cancel,
+    InputAction::Rotate,
+    InputAction::Undo,
+    InputAction::Pause,
+    InputAction::NextZone,
+    InputAction::Up,
+    InputAction::Down,
+    InputAction::Left,
+    InputAction::Right,
+    InputAction::Confirm,
+    InputAction::Attach,
+    InputAction::Upgrade,
+    InputAction::Move,
+    InputAction::Deactivate,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Binding {
+    Key(geng::Key),
+    Mouse(geng::MouseButton),
+}
+
+/// Actions that need the position of the cursor, so they can only be bound to mouse buttons
+pub const POINTER_ACTIONS: [InputAction; 2] = [InputAction::Select, InputAction::Detach];
+
+/// Pairs of actions that may share a binding, since they are never used at the same time
+const SHARED_BINDINGS: [(InputAction, InputAction); 2] = [
+    // Escape cancels the interaction in progress and pauses otherwise
+    (InputAction::Cancel, InputAction::Pause),
+    // Right click detaches a cell when idle and cancels otherwise
+    (InputAction::Detach, InputAction::Cancel),
+];
+
+/// Keys and mouse buttons bound to each of the input actions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bindings(pub HashMap<InputAction, Vec<Binding>>);
+
+impl Default for Bindings {
+    fn default() -> Self {
+        use geng::{Key, MouseButton};
+        use Binding::{Key as K, Mouse as M};
+        let bindings = [
+            (InputAction::EndTurn, vec![K(Key::Enter)]),
+            (InputAction::Select, vec![M(MouseButton::Left)]),
+            (InputAction::Detach, vec![M(MouseButton::Right)]),
+            (
+                InputAction::Cancel,
+                vec![K(Key::Escape), M(MouseButton::Right)],
+            ),
+            (InputAction::Rotate, vec![K(Key::R)]),
+            (InputAction::Undo, vec![K(Key::Z)]),
+            (InputAction::Pause, vec![K(Key::Escape)]),
+            (InputAction::NextZone, vec![K(Key::Tab)]),
+            (InputAction::Up, vec![K(Key::Up)]),
+            (InputAction::Down, vec![K(Key::Down)]),
+            (InputAction::Left, vec![K(Key::Left)]),
+            (InputAction::Right, vec![K(Key::Right)]),
+            (InputAction::Confirm, vec![K(Key::Space)]),
+            (InputAction::Attach, vec![K(Key::A)]),
+            (InputAction::Upgrade, vec![K(Key::U)]),
+            (InputAction::Move, vec![K(Key::M)]),
+            (InputAction::Deactivate, vec![K(Key::D)]),
+        ];
+        Self(bindings.into_iter().collect())
+    }
+}
+
+impl Bindings {
+    /// Loads the bindings, the actions missing from the file keep the default bindings.
+    /// Falls back to the defaults if the file binds something the controls screen would reject.
+    pub fn load() -> Self {
+        let mut bindings = Self::default();
+        let loaded: Option<Self> = std::fs::read_to_string(BINDINGS_PATH)
+            .ok()
+            .and_then(|file| {
+                serde_json::from_str(&file)
+                    .map_err(|error| {
+                        error!("Failed to parse the bindings, using the defaults: {error}")
+                    })
+                    .ok()
+            });
+        if let Some(loaded) = loaded {
+            bindings.0.extend(loaded.0);
+        }
+        match bindings.validate() {
+            Ok(()) => bindings,
+            Err(error) => {
+                error!("Invalid bindings, using the defaults: {error}");
+                Self::default()
+            }
+        }
+    }
+
+    /// Checks that every binding could have been made in the controls screen
+    pub fn validate(&self) -> Result<(), String> {
+        for action in INPUT_ACTIONS {
+            for &binding in self.get(action) {
+                if POINTER_ACTIONS.contains(&action) && matches!(binding, Binding::Key(_)) {
+                    return Err(format!("{action:?} needs a mouse button"));
+                }
+                if let Some(other) = self.conflict(action, binding) {
+                    return Err(format!(
+                        "{binding} is bound to both {action:?} and {other:?}"
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn save(&self) {
+        let bindings =
+            serde_json::to_string_pretty(self).expect("Failed to serialize the bindings");
+        if let Err(error) = std::fs::write(BINDINGS_PATH, bindings) {
+            error!("Failed to save the bindings: {error}");
+        }
+    }
+
+    pub fn get(&self, action: InputAction) -> &[Binding] {
+        self.0.get(&action).map_or(&[], |bindings| bindings)
+    }
+
+    /// Whether the event presses a key or a button bound to the action
+    pub fn pressed(&self, action: InputAction, event: &geng::Event) -> bool {
+        let binding = match *event {
+            geng::Event::KeyDown { key } => Binding::Key(key),
+            geng::Event::MouseDown { button, .. } => Binding::Mouse(button),
+            _ => return false,
+        };
+        self.get(action).contains(&binding)
+    }
+
+    /// Whether the event releases a key or a button bound to the action
+    pub fn released(&self, action: InputAction, event: &geng::Event) -> bool {
+        let binding = match *event {
+            geng::Event::KeyUp { key } => Binding::Key(key),
+            geng::Event::MouseUp { button, .. } => Binding::Mouse(button),
+            _ => return false,
+        };
+        self.get(action).contains(&binding)
+    }
+
+    /// Names the keys and buttons bound to the action, for hints
+    pub fn describe(&self, action: InputAction) -> String {
+        match self.get(action) {
+            [] => "(unbound)".to_owned(),
+            bindings => bindings
+                .iter()
+                .map(|binding| binding.to_string())
+                .collect::<Vec<_>>()
+                .join(" or "),
+        }
+    }
+
+    /// Another action that the binding cannot be shared with
+    pub fn conflict(&self, action: InputAction, binding: Binding) -> Option<InputAction> {
+        INPUT_ACTIONS.iter().copied().find(|&other| {
+            other != action
+                && self.get(other).contains(&binding)
+                && !SHARED_BINDINGS.contains(&(action, other))
+                && !SHARED_BINDINGS.contains(&(other, action))
+        })
+    }
+
+    /// The first of the given actions bound to the key
+    pub fn key_action(&self, key: geng::Key, actions: &[InputAction]) -> Option<InputAction> {
+        actions
+            .iter()
+            .copied()
+            .find(|&action| self.get(action).contains(&Binding::Key(key)))
+    }
+}
+
+impl std::fmt::Display for Binding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Key(key) => write!(f, "{key:?}"),
+            Self::Mouse(button) => write!(f, "{button:?} mouse button"),
+        }
+    }
+}
@@ -1,25 +1,212 @@
 use super::*;
 
 mod controller;
-mod model;
-mod render;
+pub mod gesture;
+pub mod input;
+pub mod model;
+pub mod render;
 
 use controller::Controller;
+use model::logic::agents::Agent;
 use model::*;
+use net::{ClientMessage, Connection, ServerMessage};
 use render::Render;
 
+pub use model::{Opponent, TurnMode};
+
+/// How a match has ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameResult {
+    /// The goal of the scenario was completed or failed
+    Scenario(GoalStatus),
+    /// The local player has won or lost
+    Local { won: bool },
+    /// Used when there is no single local player, i.e. in hotseat or when spectating
+    Winner { player_a: bool },
+}
+
 pub struct Game {
     render: Render,
     model: Model,
     controller: Controller,
+    /// Connection to the server that owns the model, if playing online
+    connection: Option<Connection>,
+    /// Spectators only watch the game and cannot act
+    spectating: bool,
+    /// The puzzle being played with the turn it started on
+    scenario: Option<(Scenario, Turns)>,
+    /// Controls player B when the opponent is external
+    agent: Option<Box<dyn Agent>>,
+    /// The model before each local action of the current turn, to undo them
+    undo: Vec<Model>,
 }
 
 impl Game {
-    pub fn new(geng: &Geng, assets: &Rc<Assets>) -> Self {
+    pub fn new(geng: &Geng, assets: &Rc<Assets>, turn_mode: TurnMode, opponent: Opponent) -> Self {
         Self {
             render: Render::new(geng, assets),
-            model: Model::new(),
+            model: Model::with_mode(turn_mode, opponent),
             controller: Controller::new(),
+            connection: None,
+            spectating: false,
+            scenario: None,
+            agent: None,
+            undo: Vec::new(),
+        }
+    }
+
+    /// Continues a previously saved game
+    pub fn new_saved(geng: &Geng, assets: &Rc<Assets>, model: Model) -> Self {
+        let (turn_mode, opponent) = (model.turn_mode, model.opponent);
+        Self {
+            model,
+            ..Self::new(geng, assets, turn_mode, opponent)
+        }
+    }
+
+    /// Creates a game from an authored position against the AI
+    pub fn new_scenario(geng: &Geng, assets: &Rc<Assets>, scenario: Scenario) -> Self {
+        let model = Model::from_scenario(&scenario, Opponent::Ai);
+        Self {
+            scenario: Some((scenario, model.turn)),
+            model,
+            ..Self::new(geng, assets, TurnMode::Alternating, Opponent::Ai)
+        }
+    }
+
+    /// Creates a campaign battle against the given agent
+    pub fn new_campaign(
+        geng: &Geng,
+        assets: &Rc<Assets>,
+        scenario: Scenario,
+        agent: Box<dyn Agent>,
+    ) -> Self {
+        let model = Model::from_scenario(&scenario, Opponent::External);
+        Self {
+            scenario: Some((scenario, model.turn)),
+            model,
+            agent: Some(agent),
+            ..Self::new(geng, assets, TurnMode::Alternating, Opponent::External)
+        }
+    }
+
+    /// Lets the external agent play while it is their turn
+    fn run_agent(&mut self) {
+        let agent = match &mut self.agent {
+            Some(agent) => agent,
+            None => return,
+        };
+        let agent_turn = match self.model.turn_mode {
+            TurnMode::Alternating => !self.model.player_a_turn,
+            TurnMode::Simultaneous => {
+                self.model.submitted_a.is_some() && self.model.submitted_b.is_none()
+            }
+        };
+        if !agent_turn {
+            return;
+        }
+        let mut actions = agent.act(&self.model.view(Some(false)));
+        actions.retain(|action| !matches!(action, PlayerAction::EndTurn));
+        match self.model.turn_mode {
+            TurnMode::Alternating => {
+                for action in actions {
+                    self.model.handle_player_action(action);
+                }
+                self.model.handle_player_action(PlayerAction::EndTurn);
+            }
+            TurnMode::Simultaneous => self.model.submit_actions(false, actions),
+        }
+    }
+
+    fn goal_status(&self) -> Option<GoalStatus> {
+        self.scenario
+            .as_ref()
+            .map(|(scenario, start_turn)| scenario.goal.status(&self.model, *start_turn))
+    }
+
+    /// Returns the result once the match is over
+    pub fn result(&self) -> Option<GameResult> {
+        if let Some(status) = self.goal_status() {
+            return match status {
+                GoalStatus::InProgress => None,
+                _ => Some(GameResult::Scenario(status)),
+            };
+        }
+        let winner = self.model.winner()?;
+        let result = match self.model.opponent {
+            _ if self.spectating => GameResult::Winner { player_a: winner },
+            Opponent::Human => GameResult::Winner { player_a: winner },
+            _ => GameResult::Local {
+                won: winner == self.model.perspective(),
+            },
+        };
+        Some(result)
+    }
+
+    /// Number of turns played so far, counted like in the HUD
+    pub fn turn(&self) -> Turns {
+        self.model.displayed_turn()
+    }
+
+    /// Returns the model to be saved, if the game can be continued from it later.
+    /// Online games, puzzles and campaign battles cannot be saved.
+    pub fn save(&self) -> Option<&Model> {
+        if self.connection.is_some() || self.scenario.is_some() || self.agent.is_some() {
+            return None;
+        }
+        Some(&self.model)
+    }
+
+    /// Whether the player is not in the middle of an interaction like dragging a shape
+    pub fn is_idle(&self) -> bool {
+        self.controller.is_idle()
+    }
+
+    /// Creates a game that sends actions to the server instead of applying them locally
+    pub fn new_remote(
+        geng: &Geng,
+        assets: &Rc<Assets>,
+        mut connection: Connection,
+        spectate: bool,
+    ) -> Self {
+        if spectate {
+            connection.send(ClientMessage::Spectate);
+        }
+        Self {
+            connection: Some(connection),
+            ..Self::new(
+                geng,
+                assets,
+                TurnMode::Alternating,
+                Opponent::Remote { player_a: true },
+            )
+        }
+    }
+
+    /// The part of the model visible to the local player
+    fn view(&self, model: &Model) -> PlayerView {
+        if self.spectating {
+            model.view(None)
+        } else {
+            model.view(Some(model.perspective()))
+        }
+    }
+
+    fn handle_server_message(&mut self, message: ServerMessage) {
+        match message {
+            ServerMessage::Setup { seat } => {
+                self.spectating = seat.is_none();
+                if let Some(player_a) = seat {
+                    self.model.opponent = Opponent::Remote { player_a };
+                }
+            }
+            ServerMessage::View(view) => {
+                // The server only sends the visible part of the model
+                let opponent = self.model.opponent;
+                self.model = view.model;
+                self.model.opponent = opponent;
+            }
+            ServerMessage::Update(update) => update.apply(&mut self.model),
         }
     }
 }
@@ -27,20 +214,71 @@ impl Game {
 impl geng::State for Game {
     fn draw(&mut self, framebuffer: &mut ugli::Framebuffer) {
         ugli::clear(framebuffer, Some(Color::BLACK), None);
-        self.render.draw(&self.model, framebuffer);
+        // The plan of the local player is shown as if it was already made
+        let planned = self.model.planned_model();
+        self.render.planned_attacks = self
+            .model
+            .planned
+            .iter()
+            .filter_map(|action| match *action {
+                PlayerAction::Attack { weapon, target } => Some((weapon, target)),
+                _ => None,
+            })
+            .collect();
+        let view = self.view(planned.as_ref().unwrap_or(&self.model));
+        self.render.draw(&view, framebuffer);
+
+        if let Some((scenario, _)) = &self.scenario {
+            self.render.draw_message(&scenario.description, framebuffer);
+        }
     }
 
     fn handle_event(&mut self, event: geng::Event) {
-        for action in self
-            .controller
-            .handle_event(&self.model, &mut self.render, event)
-        {
-            self.model.handle_player_action(action);
+        if self.spectating || self.result().is_some() {
+            return;
+        }
+        if self.connection.is_none() && self.controller.undo_pressed(&event) {
+            if let Some(model) = self.undo.pop() {
+                self.model = model;
+            }
+            return;
+        }
+        // The agent may have to move first, the input is meant for the local player
+        self.run_agent();
+        let planned = self.model.planned_model();
+        let model = planned.as_ref().unwrap_or(&self.model);
+        for action in self.controller.handle_event(model, &mut self.render, event) {
+            match &mut self.connection {
+                Some(connection) => connection.send(ClientMessage::Action(action)),
+                None => {
+                    // Actions of a finished turn cannot be taken back
+                    let end_turn = matches!(action, PlayerAction::EndTurn);
+                    let before = self.model.clone();
+                    if self.model.handle_player_action(action) && !end_turn {
+                        self.undo.push(before);
+                    } else if end_turn {
+                        self.undo.clear();
+                    }
+                }
+            }
         }
+        self.run_agent();
     }
 
     fn update(&mut self, delta_time: f64) {
+        if self.result().is_none() {
+            self.run_agent();
+        }
+        self.controller.update(delta_time, &mut self.render);
         let delta_time = Time::new(delta_time as _);
         self.model.update(delta_time);
+
+        let messages: Vec<ServerMessage> = match &mut self.connection {
+            Some(connection) => std::iter::from_fn(|| connection.try_recv()).collect(),
+            None => vec![],
+        };
+        for message in messages {
+            self.handle_server_message(message);
+        }
     }
 }
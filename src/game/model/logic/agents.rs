@@ -0,0 +1,219 @@
+use super::*;
+
+/// Something that decides the actions of a player for a turn
+pub trait Agent {
+    fn name(&self) -> &str;
+    /// Returns the actions for the turn of the viewer, without the final `EndTurn`
+    fn act(&mut self, view: &PlayerView) -> Vec<PlayerAction>;
+}
+
+pub const AGENT_NAMES: [&str; 4] = ["basic", "aggressive", "random", "passive"];
+
+/// Creates an agent from its name
+pub fn agent_by_name(name: &str, seed: u64) -> Option<Box<dyn Agent>> {
+    match name {
+        "basic" => Some(Box::new(BasicAgent)),
+        "aggressive" => Some(Box::new(AggressiveAgent)),
+        "random" => Some(Box::new(RandomAgent::new(seed))),
+        "passive" => Some(Box::new(PassiveAgent)),
+        _ => None,
+    }
+}
+
+/// Returns the shapes in the order of their ids, collections iterate in hash order
+/// and the choices of the agents should only depend on their seed
+pub fn by_id(shapes: &Collection<AliveShape>) -> Vec<&AliveShape> {
+    let mut shapes: Vec<&AliveShape> = shapes.iter().collect();
+    shapes.sort_by_key(|shape| shape.id);
+    shapes
+}
+
+/// The default opponent: builds hexagons and advances
+pub struct BasicAgent;
+
+impl Agent for BasicAgent {
+    fn name(&self) -> &str {
+        "basic"
+    }
+
+    fn act(&mut self, view: &PlayerView) -> Vec<PlayerAction> {
+        enemy_ai::enemy_ai(view)
+    }
+}
+
+/// Builds like the basic agent, and attacks whenever the exchange is in its favour
+pub struct AggressiveAgent;
+
+impl Agent for AggressiveAgent {
+    fn name(&self) -> &str {
+        "aggressive"
+    }
+
+    fn act(&mut self, view: &PlayerView) -> Vec<PlayerAction> {
+        let mut actions = enemy_ai::enemy_ai(view);
+        let player_a = match view.viewer() {
+            Some(player_a) => player_a,
+            None => return actions,
+        };
+        let model = &view.model;
+        let opponent = model.player(!player_a);
+        let mut targets: Vec<Id> = opponent
+            .active_shapes
+            .0
+            .iter()
+            .map(|shape| shape.id)
+            .chain(opponent.shape_farm.plants.iter().map(|plant| plant.id))
+            .collect();
+        targets.sort();
+
+        // Attacks go before the moves, so that they are checked from the current positions
+        let moves = actions
+            .iter()
+            .position(|action| matches!(action, PlayerAction::MoveShape { .. }));
+        let mut attacks = Vec::new();
+        let mut destroyed = HashSet::new();
+        for weapon in by_id(&model.player(player_a).active_shapes.0) {
+            let best = targets
+                .iter()
+                .filter(|target| !destroyed.contains(*target))
+                .filter_map(|&target| {
+                    let preview = model.preview_attack(player_a, weapon.id, target)?;
+                    Some((target, preview, attack_score(&preview)))
+                })
+                .filter(|(_, _, score)| *score > 0)
+                .max_by_key(|(_, _, score)| *score);
+            if let Some((target, preview, _)) = best {
+                if !preview.target_survives {
+                    destroyed.insert(target);
+                }
+                attacks.push(PlayerAction::Attack {
+                    weapon: weapon.id,
+                    target,
+                });
+            }
+        }
+        let index = moves.unwrap_or(actions.len());
+        actions.splice(index..index, attacks);
+        actions
+    }
+}
+
+/// How much an attack is worth to the attacker, destroying plants matters the most
+fn attack_score(preview: &AttackPreview) -> i64 {
+    let mut score = preview.target_loss as i64 - preview.weapon_loss as i64;
+    score += preview.plant_delay as i64;
+    if !preview.target_survives {
+        score += if preview.target_loss == 0 { 100 } else { 5 };
+    }
+    if !preview.weapon_survives {
+        score -= 5;
+    }
+    score
+}
+
+/// Does nothing, useful as a baseline
+pub struct PassiveAgent;
+
+impl Agent for PassiveAgent {
+    fn name(&self) -> &str {
+        "passive"
+    }
+
+    fn act(&mut self, _view: &PlayerView) -> Vec<PlayerAction> {
+        vec![]
+    }
+}
+
+/// Makes random moves
+pub struct RandomAgent {
+    rng: StdRng,
+}
+
+impl RandomAgent {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Agent for RandomAgent {
+    fn name(&self) -> &str {
+        "random"
+    }
+
+    fn act(&mut self, view: &PlayerView) -> Vec<PlayerAction> {
+        let player_a = match view.viewer() {
+            Some(player_a) => player_a,
+            None => return vec![],
+        };
+        let player = view.model.player(player_a);
+        let opponent = view.model.player(!player_a);
+        let mut actions = Vec::new();
+
+        for shape in by_id(&player.shape_buffer.0) {
+            if self.rng.gen_bool(0.5) {
+                actions.push(PlayerAction::ActivateShape(shape.id));
+            }
+        }
+
+        let active = by_id(&player.active_shapes.0);
+        for shape in &active {
+            let pos = match shape.board_pos {
+                Some(pos) => pos,
+                None => continue,
+            };
+            match self.rng.gen_range(0..4) {
+                0 => {
+                    // Move to a random neighbouring cell
+                    let (depth, row) = [(1, 0), (-1, 0), (0, 1), (0, -1)][self.rng.gen_range(0..4)];
+                    actions.push(PlayerAction::MoveShape {
+                        shape: shape.id,
+                        pos: BoardPos {
+                            depth: pos.depth + depth,
+                            row: pos.row + row,
+                        },
+                    });
+                }
+                1 => {
+                    // Grow another shape
+                    let triangle = active
+                        .iter()
+                        .find(|other| other.id != shape.id && other.shape.0.len() == 1);
+                    let target_pos = shape
+                        .shape
+                        .boundary()
+                        .collect::<Vec<_>>()
+                        .choose(&mut self.rng)
+                        .copied();
+                    if let (Some(triangle), Some(pos)) = (triangle, target_pos) {
+                        actions.push(PlayerAction::AttachShape {
+                            triangle: triangle.id,
+                            target: shape.id,
+                            pos,
+                        });
+                    }
+                }
+                _ => {
+                    // Attack a random target
+                    let mut targets: Vec<Id> = opponent
+                        .active_shapes
+                        .0
+                        .iter()
+                        .map(|shape| shape.id)
+                        .chain(opponent.shape_farm.plants.iter().map(|plant| plant.id))
+                        .collect();
+                    targets.sort();
+                    if let Some(&target) = targets.choose(&mut self.rng) {
+                        actions.push(PlayerAction::Attack {
+                            weapon: shape.id,
+                            target,
+                        });
+                    }
+                }
+            }
+        }
+
+        actions
+    }
+}
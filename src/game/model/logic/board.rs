@@ -0,0 +1,44 @@
+use super::*;
+
+impl BoardPos {
+    pub fn is_valid(&self) -> bool {
+        (0..BOARD_DEPTH).contains(&self.depth) && (0..BOARD_ROWS).contains(&self.row)
+    }
+
+    /// Distance between positions on the same side of the center line
+    pub fn distance(&self, other: &Self) -> i64 {
+        (self.depth - other.depth).abs() + (self.row - other.row).abs()
+    }
+
+    /// Distance to a position on the opposite side of the center line
+    pub fn distance_across(&self, other: &Self) -> i64 {
+        self.depth + other.depth + 1 + (self.row - other.row).abs()
+    }
+
+    /// Distance to the plants on the opposite side of the center line
+    pub fn distance_to_farm(&self) -> i64 {
+        self.depth + BOARD_DEPTH + 1
+    }
+}
+
+impl ActiveShapes {
+    pub fn is_free(&self, pos: BoardPos) -> bool {
+        pos.is_valid() && !self.0.iter().any(|shape| shape.board_pos == Some(pos))
+    }
+
+    /// Finds a free cell, preferring the ones furthest from the center line
+    pub fn free_cell(&self) -> Option<BoardPos> {
+        (0..BOARD_DEPTH)
+            .rev()
+            .flat_map(|depth| (0..BOARD_ROWS).map(move |row| BoardPos { depth, row }))
+            .find(|&pos| self.is_free(pos))
+    }
+
+    /// Whether the given row is covered by a shape closer to the center line than `depth`
+    pub fn is_blocked(&self, row: i64, depth: i64) -> bool {
+        self.0
+            .iter()
+            .filter_map(|shape| shape.board_pos)
+            .any(|pos| pos.row == row && pos.depth < depth)
+    }
+}
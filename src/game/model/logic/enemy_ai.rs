@@ -1,13 +1,13 @@
 use super::*;
 
-pub fn enemy_ai(model: &Model) -> Vec<PlayerAction> {
-    let player = &model.player_b;
+pub fn enemy_ai(view: &PlayerView) -> Vec<PlayerAction> {
+    let player = view.model.player(view.viewer().unwrap_or(false));
     let target_shape =
         [(0, 0), (1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0)].map(|(x, y)| TriPos { x, y });
 
     let mut actions = Vec::new();
     let mut build = Vec::new();
-    for shape in &player.shape_buffer.0 {
+    for shape in agents::by_id(&player.shape_buffer.0) {
         if shape.shape.0.is_empty() {
             continue;
         }
@@ -24,10 +24,8 @@ pub fn enemy_ai(model: &Model) -> Vec<PlayerAction> {
         }
     }
 
-    for (base_id, base_size) in player
-        .active_shapes
-        .0
-        .iter()
+    for (base_id, base_size) in agents::by_id(&player.active_shapes.0)
+        .into_iter()
         .map(|shape| (shape.id, shape.shape.0.len()))
         .filter(|(_, len)| *len < 6)
     {
@@ -51,5 +49,21 @@ pub fn enemy_ai(model: &Model) -> Vec<PlayerAction> {
         });
     }
 
+    // Advance towards the center line
+    for shape in agents::by_id(&player.active_shapes.0) {
+        if let Some(pos) = shape.board_pos.filter(|pos| pos.depth > 0) {
+            let pos = BoardPos {
+                depth: pos.depth - 1,
+                ..pos
+            };
+            if player.active_shapes.is_free(pos) {
+                actions.push(PlayerAction::MoveShape {
+                    shape: shape.id,
+                    pos,
+                });
+            }
+        }
+    }
+
     actions
 }
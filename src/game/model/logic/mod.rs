@@ -1,34 +1,53 @@
 use super::*;
-use geng::prelude::itertools::Itertools;
 
+pub mod agents;
+mod board;
 mod enemy_ai;
+mod simultaneous;
 mod triangular;
 
+pub use board::*;
 pub use triangular::*;
 
 impl Model {
     pub fn update(&mut self, _delta_time: Time) {}
 
-    pub fn handle_player_action(&mut self, action: PlayerAction) {
-        if self.player_a_turn {
-            self.handle_action(action);
+    /// Returns whether the action was accepted.
+    /// In the simultaneous mode actions are only planned, and checked when the turn is resolved.
+    pub fn handle_player_action(&mut self, action: PlayerAction) -> bool {
+        if let TurnMode::Simultaneous = self.turn_mode {
+            self.plan_action(action);
+            return true;
+        }
+        if self.player_a_turn || matches!(self.opponent, Opponent::Human | Opponent::External) {
+            self.handle_action(action)
         } else {
             match action {
                 PlayerAction::EndTurn => {
-                    for action in enemy_ai::enemy_ai(self) {
+                    for action in enemy_ai::enemy_ai(&self.view(Some(false))) {
                         if let PlayerAction::EndTurn = action {
                             break;
                         }
                         self.handle_action(action);
                     }
-                    self.handle_action(PlayerAction::EndTurn);
+                    self.handle_action(PlayerAction::EndTurn)
                 }
-                _ => {}
+                _ => false,
             }
         }
     }
 
-    fn handle_action(&mut self, action: PlayerAction) {
+    /// Applies an action received over the network.
+    /// Returns `false` if it is not the given player's turn or the action is invalid.
+    pub fn handle_remote_action(&mut self, player_a: bool, action: PlayerAction) -> bool {
+        if self.player_a_turn != player_a {
+            return false;
+        }
+        self.handle_action(action)
+    }
+
+    /// Returns whether the action was valid and has been made
+    fn handle_action(&mut self, action: PlayerAction) -> bool {
         {
             let player = if self.player_a_turn {
                 "Player"
@@ -38,7 +57,10 @@ impl Model {
             info!("{player}'s turn. Handling action: {action:?}");
         }
         match action {
-            PlayerAction::EndTurn => self.tick(),
+            PlayerAction::EndTurn => {
+                self.tick();
+                true
+            }
             PlayerAction::ActivateShape(shape_id) => self.activate_shape(shape_id),
             PlayerAction::DeactivateShape(shape_id) => self.deactivate_shape(shape_id),
             PlayerAction::AttachShape {
@@ -46,15 +68,31 @@ impl Model {
                 target,
                 pos,
             } => self.attach_shape(triangle, target, pos),
+            PlayerAction::MergeShapes {
+                source,
+                target,
+                rotation,
+                offset,
+            } => self.merge_shapes(source, target, rotation, offset),
+            PlayerAction::DetachCell { shape, pos } => self.detach_cell(shape, pos),
             PlayerAction::UpgradePlant {
                 source_shape,
                 target_plant,
             } => self.upgrade_plant(source_shape, target_plant),
             PlayerAction::Attack { weapon, target } => self.attack(weapon, target),
+            PlayerAction::MoveShape { shape, pos } => self.move_shape(shape, pos),
         }
     }
 
     fn tick(&mut self) {
+        self.tick_active();
+        self.player_a_turn = !self.player_a_turn;
+        self.turn += 1;
+    }
+
+    /// Produces new shapes from the plants of the active player
+    /// and restores their shapes for the next turn
+    fn tick_active(&mut self) {
         let active = if self.player_a_turn {
             &mut self.player_a
         } else {
@@ -62,13 +100,22 @@ impl Model {
         };
         for plant in &mut active.shape_farm.plants {
             if plant.tick() {
-                active.shape_buffer.0.insert(AliveShape {
-                    id: self.id_gen.next(),
-                    shape: plant.shape.clone(),
-                });
+                active.shape_buffer.0.insert(AliveShape::new(
+                    self.id_gen.next(),
+                    plant.shape.clone(),
+                    None,
+                ));
             }
         }
-        self.player_a_turn = !self.player_a_turn;
+        for shape in active
+            .shape_buffer
+            .0
+            .iter_mut()
+            .chain(active.active_shapes.0.iter_mut())
+        {
+            shape.exhausted = false;
+            shape.moved = false;
+        }
     }
 
     /// First return player is active, the other is not
@@ -80,22 +127,63 @@ impl Model {
         }
     }
 
-    fn activate_shape(&mut self, shape_id: Id) {
+    fn activate_shape(&mut self, shape_id: Id) -> bool {
         let (active, _) = self.active_player();
-        if let Some(shape) = active.shape_buffer.0.remove(&shape_id) {
-            active.active_shapes.0.insert(shape);
+        let board_pos = match active.active_shapes.free_cell() {
+            Some(pos) => pos,
+            None => return false,
+        };
+        match active.shape_buffer.0.remove(&shape_id) {
+            Some(mut shape) => {
+                shape.board_pos = Some(board_pos);
+                active.active_shapes.0.insert(shape);
+                true
+            }
+            None => false,
         }
     }
 
-    fn deactivate_shape(&mut self, shape_id: Id) {
+    fn deactivate_shape(&mut self, shape_id: Id) -> bool {
         let (active, _) = self.active_player();
-        if let Some(shape) = active.active_shapes.0.remove(&shape_id) {
-            active.shape_buffer.0.insert(shape);
+        match active.active_shapes.0.remove(&shape_id) {
+            Some(mut shape) => {
+                shape.board_pos = None;
+                active.shape_buffer.0.insert(shape);
+                true
+            }
+            None => false,
         }
     }
 
-    fn attach_shape(&mut self, triangle: Id, target: Id, pos: TriPos) {
+    fn move_shape(&mut self, shape_id: Id, pos: BoardPos) -> bool {
+        let (active, _) = self.active_player();
+        if !active.active_shapes.is_free(pos) {
+            return false;
+        }
+        match active
+            .active_shapes
+            .0
+            .get_mut(&shape_id)
+            .filter(|shape| !shape.moved)
+        {
+            Some(shape) => {
+                let current = shape.board_pos.expect("Active shape is not on the board");
+                if current.distance(&pos) != 1 {
+                    return false;
+                }
+                shape.board_pos = Some(pos);
+                shape.moved = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn attach_shape(&mut self, triangle: Id, target: Id, pos: TriPos) -> bool {
         let mut attach_impl = || -> Option<()> {
+            if triangle == target {
+                return None;
+            }
             let (active, _) = self.active_player();
             let _triangle = active
                 .active_shapes
@@ -118,12 +206,73 @@ impl Model {
                 .expect("Attached triangle disappeared");
             Some(())
         };
-        attach_impl();
+        attach_impl().is_some()
+    }
+
+    fn merge_shapes(&mut self, source: Id, target: Id, rotation: i64, offset: TriPos) -> bool {
+        let mut merge_impl = || -> Option<()> {
+            if source == target {
+                return None;
+            }
+            let (active, _) = self.active_player();
+            let (source_shape, exhausted, moved) = active
+                .active_shapes
+                .0
+                .get(&source)
+                .or_else(|| active.shape_buffer.0.get(&source))
+                .map(|shape| (shape.shape.clone(), shape.exhausted, shape.moved))?;
+            let target = active.active_shapes.0.get_mut(&target)?;
+            let merged = target.shape.merge(&source_shape, rotation, offset)?;
+
+            target.shape = merged;
+            // The merged shape has used up whatever either part has used this turn
+            target.exhausted |= exhausted;
+            target.moved |= moved;
+            active
+                .remove_shape(source)
+                .expect("Merged shape disappeared");
+            Some(())
+        };
+        merge_impl().is_some()
     }
 
-    fn upgrade_plant(&mut self, source_shape: Id, target_plant: Id) {
+    fn detach_cell(&mut self, shape_id: Id, pos: TriPos) -> bool {
+        let mut detach_impl = || -> Option<()> {
+            let (active, _) = self.active_player();
+            let shape = active
+                .active_shapes
+                .0
+                .get_mut(&shape_id)
+                .or_else(|| active.shape_buffer.0.get_mut(&shape_id))
+                .filter(|shape| shape.shape.0.len() > 1)?;
+            let index = shape.shape.0.iter().position(|&cell| cell == pos)?;
+            let mut remaining = shape.shape.clone();
+            remaining.0.remove(index);
+            if !remaining.is_connected() {
+                return None;
+            }
+            remaining.sort_connected();
+            shape.shape = remaining;
+            let (exhausted, moved) = (shape.exhausted, shape.moved);
+            // Only successful detaches use up an id
+            let triangle = AliveShape {
+                id: self.id_gen.next(),
+                shape: Shape(vec![TriPos { x: 0, y: 0 }]),
+                exhausted,
+                moved,
+                board_pos: None,
+            };
+            let (active, _) = self.active_player();
+            active.shape_buffer.0.insert(triangle);
+            Some(())
+        };
+        detach_impl().is_some()
+    }
+
+    fn upgrade_plant(&mut self, source_shape: Id, target_plant: Id) -> bool {
         let mut upgrade_impl = || -> Option<()> {
             let (active, _) = self.active_player();
+            active.shape_farm.plants.get(&target_plant)?;
             let source = active.remove_shape(source_shape)?;
             let plant = active.shape_farm.plants.get_mut(&target_plant)?;
 
@@ -131,7 +280,8 @@ impl Model {
                 0 => return None,
                 1 => {
                     // Increase efficiency
-                    todo!()
+                    plant.cooldown = plant.cooldown.saturating_sub(1).max(1);
+                    plant.time_left = plant.time_left.min(plant.cooldown);
                 }
                 _ => {
                     // Change shape
@@ -141,13 +291,16 @@ impl Model {
             }
             Some(())
         };
-        upgrade_impl();
+        upgrade_impl().is_some()
     }
 
-    fn attack(&mut self, weapon_id: Id, target_id: Id) {
+    fn attack(&mut self, weapon_id: Id, target_id: Id) -> bool {
         let mut attack_impl = || -> Option<()> {
             let (active, inactive) = self.active_player();
-            let weapon = &mut active.active_shapes.0.get_mut(&weapon_id)?.shape;
+            let weapon = active.active_shapes.0.get_mut(&weapon_id)?;
+            check_attack(weapon, inactive, target_id)?;
+            weapon.exhausted = true;
+            let weapon = &mut weapon.shape;
             match inactive.active_shapes.0.get_mut(&target_id) {
                 Some(target_active) => {
                     let (weapon_alive, target_alive) =
@@ -188,10 +341,79 @@ impl Model {
             }
             Some(())
         };
-        attack_impl();
+        attack_impl().is_some()
     }
 }
 
+impl Model {
+    /// Predicts the outcome of an attack by the given player without changing the model.
+    /// Returns `None` if the attack is not possible.
+    pub fn preview_attack(
+        &self,
+        player_a: bool,
+        weapon_id: Id,
+        target_id: Id,
+    ) -> Option<AttackPreview> {
+        let weapon = self.player(player_a).active_shapes.0.get(&weapon_id)?;
+        let target_player = self.player(!player_a);
+        check_attack(weapon, target_player, target_id)?;
+        let mut weapon_shape = weapon.shape.clone();
+        let preview = match target_player.active_shapes.0.get(&target_id) {
+            Some(target) => {
+                let mut target_shape = target.shape.clone();
+                let (weapon_survives, target_survives) =
+                    attack_active(&mut weapon_shape, &mut target_shape);
+                AttackPreview {
+                    weapon_loss: weapon.shape.0.len() - weapon_shape.0.len(),
+                    target_loss: target.shape.0.len() - target_shape.0.len(),
+                    weapon_survives,
+                    target_survives,
+                    plant_delay: 0,
+                }
+            }
+            None => {
+                let plant = target_player.shape_farm.plants.get(&target_id)?;
+                let mut target_plant = plant.clone();
+                let (weapon_survives, target_survives) =
+                    attack_plant(&mut weapon_shape, &mut target_plant);
+                AttackPreview {
+                    weapon_loss: weapon.shape.0.len() - weapon_shape.0.len(),
+                    target_loss: 0,
+                    weapon_survives,
+                    target_survives,
+                    plant_delay: target_plant.time_left - plant.time_left,
+                }
+            }
+        };
+        Some(preview)
+    }
+}
+
+/// Checks that the weapon is ready and can reach the target
+fn check_attack(weapon: &AliveShape, target_player: &Player, target_id: Id) -> Option<()> {
+    if weapon.exhausted {
+        return None;
+    }
+    let weapon_pos = weapon.board_pos?;
+    let in_range = match target_player.active_shapes.0.get(&target_id) {
+        Some(target) => {
+            let target_pos = target.board_pos?;
+            weapon_pos.distance_across(&target_pos) <= ATTACK_RANGE
+                && !target_player
+                    .active_shapes
+                    .is_blocked(target_pos.row, target_pos.depth)
+        }
+        None => {
+            target_player.shape_farm.plants.get(&target_id)?;
+            weapon_pos.distance_to_farm() <= ATTACK_RANGE
+                && !target_player
+                    .active_shapes
+                    .is_blocked(weapon_pos.row, BOARD_DEPTH)
+        }
+    };
+    in_range.then_some(())
+}
+
 /// Returns who survived
 fn attack_active(weapon: &mut Shape, target: &mut Shape) -> (bool, bool) {
     let attack_damage = weapon.0.len();
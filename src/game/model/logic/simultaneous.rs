@@ -0,0 +1,222 @@
+use super::*;
+
+/// The order in which submitted actions are resolved
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Phase {
+    Build,
+    Attack,
+}
+
+impl PlayerAction {
+    fn phase(&self) -> Option<Phase> {
+        match self {
+            PlayerAction::EndTurn => None,
+            PlayerAction::Attack { .. } => Some(Phase::Attack),
+            _ => Some(Phase::Build),
+        }
+    }
+
+    /// Whether a newer plan replaces the action, i.e. both do the same thing to the same shape
+    fn replaces(&self, other: &Self) -> bool {
+        use PlayerAction::*;
+        match (self, other) {
+            (ActivateShape(a), ActivateShape(b)) | (DeactivateShape(a), DeactivateShape(b)) => {
+                a == b
+            }
+            (AttachShape { triangle: a, .. }, AttachShape { triangle: b, .. }) => a == b,
+            (MergeShapes { source: a, .. }, MergeShapes { source: b, .. }) => a == b,
+            (
+                DetachCell {
+                    shape: a,
+                    pos: pos_a,
+                },
+                DetachCell {
+                    shape: b,
+                    pos: pos_b,
+                },
+            ) => a == b && pos_a == pos_b,
+            (
+                UpgradePlant {
+                    source_shape: a, ..
+                },
+                UpgradePlant {
+                    source_shape: b, ..
+                },
+            ) => a == b,
+            (Attack { weapon: a, .. }, Attack { weapon: b, .. }) => a == b,
+            (MoveShape { shape: a, .. }, MoveShape { shape: b, .. }) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Model {
+    /// Collects the local player's actions until the end of the turn
+    pub(super) fn plan_action(&mut self, action: PlayerAction) {
+        match action {
+            PlayerAction::EndTurn => {
+                let actions = std::mem::take(&mut self.planned);
+                self.submit_actions(self.player_a_turn, actions);
+                match self.opponent {
+                    Opponent::Ai => {
+                        if self.submitted_b.is_none() {
+                            let actions = enemy_ai::enemy_ai(&self.view(Some(false)));
+                            self.submit_actions(false, actions);
+                        }
+                    }
+                    Opponent::Human => {
+                        // Let the other player plan their turn
+                        if self.submitted_a.is_some() {
+                            self.player_a_turn = false;
+                        }
+                    }
+                    // The other player submits on their own
+                    Opponent::External | Opponent::Remote { .. } => {}
+                }
+            }
+            action => {
+                self.planned.retain(|planned| !action.replaces(planned));
+                self.planned.push(action);
+            }
+        }
+    }
+
+    /// The model as the planning player will see it once their turn is resolved:
+    /// the planned builds are made and the weapons of the planned attacks are exhausted.
+    /// Returns `None` if nothing is planned.
+    pub fn planned_model(&self) -> Option<Self> {
+        if self.planned.is_empty() {
+            return None;
+        }
+        let mut model = self.clone();
+        for action in std::mem::take(&mut model.planned) {
+            match action {
+                PlayerAction::Attack { weapon, .. } => {
+                    let (active, _) = model.active_player();
+                    if let Some(weapon) = active.active_shapes.0.get_mut(&weapon) {
+                        weapon.exhausted = true;
+                    }
+                }
+                action => {
+                    model.handle_action(action);
+                }
+            }
+        }
+        Some(model)
+    }
+
+    /// Submits the actions of a player for the current turn.
+    /// When both players have submitted, the turn is resolved.
+    pub fn submit_actions(&mut self, player_a: bool, actions: Vec<PlayerAction>) {
+        let submitted = if player_a {
+            &mut self.submitted_a
+        } else {
+            &mut self.submitted_b
+        };
+        if submitted.is_some() {
+            warn!("Actions for this turn have already been submitted");
+            return;
+        }
+        *submitted = Some(actions);
+
+        if self.submitted_a.is_some() && self.submitted_b.is_some() {
+            let actions_a = self.submitted_a.take().unwrap();
+            let actions_b = self.submitted_b.take().unwrap();
+            self.resolve_turn(actions_a, actions_b);
+        }
+    }
+
+    /// Resolves the turn: first both players build, then all attacks are made at once,
+    /// and finally the plants of both players grow
+    fn resolve_turn(&mut self, actions_a: Vec<PlayerAction>, actions_b: Vec<PlayerAction>) {
+        let split = |actions: Vec<PlayerAction>| -> (Vec<PlayerAction>, Vec<PlayerAction>) {
+            actions
+                .into_iter()
+                .filter(|action| action.phase().is_some())
+                .partition(|action| action.phase() == Some(Phase::Build))
+        };
+        let (builds_a, attacks_a) = split(actions_a);
+        let (builds_b, attacks_b) = split(actions_b);
+
+        self.resolved.clear();
+        for (player_a, builds) in [(true, builds_a), (false, builds_b)] {
+            self.player_a_turn = player_a;
+            for action in builds {
+                if self.handle_action(action.clone()) {
+                    self.resolved.push((player_a, action));
+                }
+            }
+        }
+
+        self.resolve_attacks([(true, attacks_a), (false, attacks_b)]);
+
+        for player_a in [true, false] {
+            self.player_a_turn = player_a;
+            self.tick_active();
+        }
+        self.player_a_turn = true;
+        // Both players have taken their turn
+        self.turn += 2;
+    }
+}
+
+impl Model {
+    /// Makes the attacks of both players at the same time: every attack is checked
+    /// against the board as it was before any of them, and the damage is dealt together,
+    /// so a weapon destroyed this turn still gets to strike back
+    fn resolve_attacks(&mut self, attacks: [(bool, Vec<PlayerAction>); 2]) {
+        let before = self.clone();
+        let mut used = HashSet::new();
+        let mut losses: HashMap<Id, usize> = HashMap::new();
+        let mut destroyed_plants = HashSet::new();
+        for (player_a, attacks) in attacks {
+            for attack in attacks {
+                let (weapon, target) = match attack {
+                    PlayerAction::Attack { weapon, target } => (weapon, target),
+                    _ => continue,
+                };
+                if !used.insert(weapon) {
+                    continue;
+                }
+                let preview = match before.preview_attack(player_a, weapon, target) {
+                    Some(preview) => preview,
+                    None => continue,
+                };
+                self.resolved.push((player_a, attack));
+                *losses.entry(weapon).or_default() += preview.weapon_loss;
+                if let Some(shape) = self.player_mut(player_a).active_shapes.0.get_mut(&weapon) {
+                    shape.exhausted = true;
+                }
+                let target_player = self.player_mut(!player_a);
+                match target_player.shape_farm.plants.get_mut(&target) {
+                    Some(plant) => {
+                        plant.time_left += preview.plant_delay;
+                        if !preview.target_survives {
+                            destroyed_plants.insert(target);
+                        }
+                    }
+                    None => *losses.entry(target).or_default() += preview.target_loss,
+                }
+            }
+        }
+
+        for player_a in [true, false] {
+            let player = self.player_mut(player_a);
+            let mut destroyed = Vec::new();
+            for shape in player.active_shapes.0.iter_mut() {
+                let loss = losses.get(&shape.id).copied().unwrap_or(0);
+                let remaining = shape.shape.0.len().saturating_sub(loss);
+                shape.shape.0.truncate(remaining);
+                if remaining == 0 {
+                    destroyed.push(shape.id);
+                }
+            }
+            for id in destroyed {
+                player.active_shapes.0.remove(&id);
+            }
+            for &id in &destroyed_plants {
+                player.shape_farm.plants.remove(&id);
+            }
+        }
+    }
+}
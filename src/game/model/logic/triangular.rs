@@ -17,6 +17,23 @@ impl TriPos {
         )
     }
 
+    /// Finds the cell whose center is closest to the point
+    pub fn from_cartesian(pos: Vec2<R32>) -> Self {
+        let row_height = 3f32.sqrt() * 0.5;
+        Self {
+            x: (pos.x.as_f32() / 0.5).round() as i64,
+            y: (pos.y.as_f32() / row_height).round() as i64,
+        }
+    }
+
+    /// Rotates the cell by `steps` * 60 degrees counterclockwise
+    /// around the top vertex of the origin cell
+    pub fn rotate(&self, steps: i64) -> Self {
+        let pivot = vec2(R32::ZERO, r32(3.0).sqrt() / r32(4.0));
+        let angle = R32::PI / r32(3.0) * r32(steps.rem_euclid(6) as _);
+        Self::from_cartesian((self.to_cartesian() - pivot).rotate(angle) + pivot)
+    }
+
     pub fn neigbours(&self) -> [TriPos; 3] {
         let dy = if self.is_upside_down() { 1 } else { -1 };
         [
@@ -65,6 +82,101 @@ impl Shape {
             .any(|tri_pos| inside_triangle(pos, tri_pos.to_vertices()))
     }
 
+    /// Returns the cell containing the position
+    pub fn cell_at(&self, pos: Vec2<R32>) -> Option<TriPos> {
+        self.0
+            .iter()
+            .copied()
+            .find(|tri_pos| inside_triangle(pos, tri_pos.to_vertices()))
+    }
+
+    /// Whether all cells are connected by edges
+    pub fn is_connected(&self) -> bool {
+        let start = match self.0.first() {
+            Some(&pos) => pos,
+            None => return true,
+        };
+        let mut visited = vec![start];
+        let mut queue = vec![start];
+        while let Some(pos) = queue.pop() {
+            for neighbour in pos.neigbours() {
+                if self.0.contains(&neighbour) && !visited.contains(&neighbour) {
+                    visited.push(neighbour);
+                    queue.push(neighbour);
+                }
+            }
+        }
+        visited.len() == self.0.len()
+    }
+
+    /// Orders the cells so that every prefix of the shape is connected,
+    /// since damage removes the cells from the end
+    pub fn sort_connected(&mut self) {
+        let start = match self.0.first() {
+            Some(&pos) => pos,
+            None => return,
+        };
+        let mut order = vec![start];
+        let mut index = 0;
+        while index < order.len() {
+            for neighbour in order[index].neigbours() {
+                if self.0.contains(&neighbour) && !order.contains(&neighbour) {
+                    order.push(neighbour);
+                }
+            }
+            index += 1;
+        }
+        // Cells that are not connected keep their order at the end
+        let rest: Vec<TriPos> = self
+            .0
+            .iter()
+            .copied()
+            .filter(|pos| !order.contains(pos))
+            .collect();
+        order.extend(rest);
+        self.0 = order;
+    }
+
+    /// Returns the cells of the shape rotated by `rotation` * 60 degrees and then moved by `offset`
+    pub fn transformed(&self, rotation: i64, offset: TriPos) -> Self {
+        Self(
+            self.0
+                .iter()
+                .map(|pos| {
+                    let pos = pos.rotate(rotation);
+                    TriPos {
+                        x: pos.x + offset.x,
+                        y: pos.y + offset.y,
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    /// Tries to place the other shape next to this one.
+    /// Returns the combined shape if the shapes do not overlap and share an edge.
+    pub fn merge(&self, other: &Self, rotation: i64, offset: TriPos) -> Option<Self> {
+        if (offset.x + offset.y) % 2 != 0 {
+            // Moving by that offset would flip the triangles
+            return None;
+        }
+        let other = other.transformed(rotation, offset);
+        if other.0.iter().any(|pos| self.0.contains(pos)) {
+            return None;
+        }
+        if !other
+            .0
+            .iter()
+            .any(|pos| self.boundary().any(|cell| cell == *pos))
+        {
+            return None;
+        }
+        let mut shape = self.clone();
+        shape.0.extend(other.0);
+        shape.sort_connected();
+        Some(shape)
+    }
+
     pub fn boundary<'a>(&'a self) -> impl Iterator<Item = TriPos> + 'a {
         // TODO: optimize
         self.0
@@ -1,12 +1,24 @@
 use super::*;
 
 pub mod logic;
+mod scenario;
+mod view;
+
+pub use scenario::*;
+pub use view::*;
 
 pub type Time = R32;
 pub type Turns = u64;
 pub type Id = u64;
 
-#[derive(Debug)]
+/// Number of columns in the active zone of each player
+pub const BOARD_DEPTH: i64 = 3;
+/// Number of rows in the active zone
+pub const BOARD_ROWS: i64 = 4;
+/// Maximum distance at which a shape can attack
+pub const ATTACK_RANGE: i64 = 4;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IdGenerator(Id);
 
 impl IdGenerator {
@@ -20,7 +32,7 @@ impl IdGenerator {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum PlayerAction {
     ActivateShape(Id),
     DeactivateShape(Id),
@@ -29,6 +41,18 @@ pub enum PlayerAction {
         target: Id,
         pos: TriPos,
     },
+    /// Combine two shapes, the source is rotated by `rotation` * 60 degrees
+    /// and moved by `offset` relative to the target
+    MergeShapes {
+        source: Id,
+        target: Id,
+        rotation: i64,
+        offset: TriPos,
+    },
+    DetachCell {
+        shape: Id,
+        pos: TriPos,
+    },
     UpgradePlant {
         source_shape: Id,
         target_plant: Id,
@@ -37,30 +61,99 @@ pub enum PlayerAction {
         weapon: Id,
         target: Id,
     },
+    MoveShape {
+        shape: Id,
+        pos: BoardPos,
+    },
     EndTurn,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TurnMode {
+    /// Players take turns one after another
+    Alternating,
+    /// Both players plan their turns secretly and the model resolves them together
+    Simultaneous,
+}
+
+/// Parameters of a match
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Rules {
+    pub turn_mode: TurnMode,
+    /// Turns between the harvests of the starting plant
+    pub plant_cooldown: Turns,
+    /// Turns before the first harvest of the starting plant
+    pub plant_initial_cooldown: Turns,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Opponent {
+    /// Player B is controlled by the computer
+    Ai,
+    /// Player B is controlled by a human on the same machine
+    Human,
+    /// Player B is controlled by an agent outside of the model
+    External,
+    /// Both players are connected to a server, the local one controls the given side
+    Remote { player_a: bool },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Model {
     id_gen: IdGenerator,
     pub player_a: Player,
     pub player_b: Player,
     pub player_a_turn: bool,
+    /// Number of turns played since the start of the match, counting the turns of both players,
+    /// so that it can be compared between the turn modes
+    pub turn: Turns,
+    pub turn_mode: TurnMode,
+    pub opponent: Opponent,
+    /// Actions submitted for the current turn in the simultaneous mode
+    pub submitted_a: Option<Vec<PlayerAction>>,
+    pub submitted_b: Option<Vec<PlayerAction>>,
+    /// Actions planned by the local player that are not submitted yet
+    pub planned: Vec<PlayerAction>,
+    /// Actions made when the last simultaneous turn was resolved, with the player who made them
+    #[serde(default)]
+    pub resolved: Vec<(bool, PlayerAction)>,
 }
 
-#[derive(Debug, Clone)]
+/// Predicted outcome of an attack
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AttackPreview {
+    /// Number of cells the weapon loses
+    pub weapon_loss: usize,
+    /// Number of cells the target loses, plants do not lose cells
+    pub target_loss: usize,
+    pub weapon_survives: bool,
+    pub target_survives: bool,
+    /// Turns added to the harvest of the target plant
+    pub plant_delay: Turns,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GrabbedShape {
     pub shape: AliveShape,
 }
 
 /// A position in a triangular grid
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct TriPos {
     pub x: i64,
     pub y: i64,
 }
 
-#[derive(Debug)]
+/// A cell on the board of the active zone.
+/// `depth` is counted from the center line, 0 being the closest column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BoardPos {
+    pub depth: i64,
+    pub row: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Player {
     pub shape_buffer: ShapeBuffer,
     pub shape_farm: ShapeFarm,
@@ -68,27 +161,33 @@ pub struct Player {
 }
 
 /// A shape is basically formed from cells in a triangular grid.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Shape(pub Vec<TriPos>);
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AliveShape {
     pub id: Id,
     pub shape: Shape,
+    /// Whether the shape has already attacked this turn
+    pub exhausted: bool,
+    /// Whether the shape has already moved this turn
+    pub moved: bool,
+    /// Position on the board, only present for active shapes
+    pub board_pos: Option<BoardPos>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ShapeBuffer(pub Collection<AliveShape>);
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ShapeFarm {
     pub plants: Collection<Plant>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ActiveShapes(pub Collection<AliveShape>);
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Plant {
     pub id: Id,
     pub shape: Shape,
@@ -98,21 +197,93 @@ pub struct Plant {
 
 impl Model {
     pub fn new() -> Self {
+        Self::with_mode(TurnMode::Alternating, Opponent::Ai)
+    }
+
+    pub fn with_mode(turn_mode: TurnMode, opponent: Opponent) -> Self {
+        let rules = Rules {
+            turn_mode,
+            ..Default::default()
+        };
+        Self::with_rules(&rules, opponent)
+    }
+
+    pub fn with_rules(rules: &Rules, opponent: Opponent) -> Self {
         let mut id_gen = IdGenerator::new();
         Self {
-            player_a: Player::new(&mut id_gen),
-            player_b: Player::new(&mut id_gen),
+            player_a: Player::new(&mut id_gen, rules),
+            player_b: Player::new(&mut id_gen, rules),
             player_a_turn: true,
+            turn: 0,
+            turn_mode: rules.turn_mode,
+            opponent,
+            submitted_a: None,
+            submitted_b: None,
+            planned: Vec::new(),
+            resolved: Vec::new(),
             id_gen,
         }
     }
 }
 
+impl Model {
+    pub fn player(&self, player_a: bool) -> &Player {
+        if player_a {
+            &self.player_a
+        } else {
+            &self.player_b
+        }
+    }
+
+    pub fn player_mut(&mut self, player_a: bool) -> &mut Player {
+        if player_a {
+            &mut self.player_a
+        } else {
+            &mut self.player_b
+        }
+    }
+
+    /// The turn number shown to the players, both players' turns of a round share the number
+    pub fn displayed_turn(&self) -> Turns {
+        self.turn / 2 + 1
+    }
+
+    /// Returns whose side the local controls should manipulate
+    pub fn perspective(&self) -> bool {
+        match self.opponent {
+            Opponent::Ai | Opponent::External => true,
+            Opponent::Human => self.player_a_turn,
+            Opponent::Remote { player_a } => player_a,
+        }
+    }
+
+    /// Returns the winner once one of the players has lost all of their plants
+    pub fn winner(&self) -> Option<bool> {
+        if self.player_b.shape_farm.plants.is_empty() {
+            Some(true)
+        } else if self.player_a.shape_farm.plants.is_empty() {
+            Some(false)
+        } else {
+            None
+        }
+    }
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self {
+            turn_mode: TurnMode::Alternating,
+            plant_cooldown: 2,
+            plant_initial_cooldown: 0,
+        }
+    }
+}
+
 impl Player {
-    pub fn new(id_gen: &mut IdGenerator) -> Self {
+    pub fn new(id_gen: &mut IdGenerator, rules: &Rules) -> Self {
         Self {
             shape_buffer: ShapeBuffer::new(),
-            shape_farm: ShapeFarm::new(id_gen),
+            shape_farm: ShapeFarm::new(id_gen, rules),
             active_shapes: ActiveShapes::new(),
         }
     }
@@ -125,13 +296,13 @@ impl ShapeBuffer {
 }
 
 impl ShapeFarm {
-    pub fn new(id_gen: &mut IdGenerator) -> Self {
+    pub fn new(id_gen: &mut IdGenerator, rules: &Rules) -> Self {
         let mut plants = Collection::new();
         plants.insert(Plant::new(
             id_gen.next(),
             Shape(vec![TriPos { x: 0, y: 0 }]),
-            2,
-            0,
+            rules.plant_cooldown,
+            rules.plant_initial_cooldown,
         ));
         Self { plants }
     }
@@ -143,6 +314,18 @@ impl ActiveShapes {
     }
 }
 
+impl AliveShape {
+    pub fn new(id: Id, shape: Shape, board_pos: Option<BoardPos>) -> Self {
+        Self {
+            id,
+            shape,
+            exhausted: false,
+            moved: false,
+            board_pos,
+        }
+    }
+}
+
 impl Plant {
     pub fn new(id: Id, shape: Shape, cooldown: Turns, initial_cooldown: Turns) -> Self {
         Self {
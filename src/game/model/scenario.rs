@@ -0,0 +1,175 @@
+use super::*;
+
+/// An authored starting position with a goal for player A
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Scenario {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub player_a: PlayerSetup,
+    pub player_b: PlayerSetup,
+    #[serde(default = "default_player_a_turn")]
+    pub player_a_turn: bool,
+    pub goal: Goal,
+}
+
+fn default_player_a_turn() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlayerSetup {
+    pub plants: Vec<PlantSetup>,
+    pub buffer: Vec<Shape>,
+    pub active: Vec<ActiveSetup>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlantSetup {
+    pub shape: Shape,
+    pub cooldown: Turns,
+    #[serde(default)]
+    pub time_left: Turns,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActiveSetup {
+    pub shape: Shape,
+    pub pos: BoardPos,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum Goal {
+    /// Destroy all enemy plants within the given number of your turns
+    DestroyPlants { turns: Turns },
+    /// Keep at least one plant alive for the given number of your turns
+    Survive { turns: Turns },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GoalStatus {
+    InProgress,
+    Completed,
+    Failed,
+}
+
+impl Scenario {
+    /// Checks that the scenario describes a position the game can be played from
+    pub fn validate(&self) -> Result<(), String> {
+        for (side, setup) in [("player A", &self.player_a), ("player B", &self.player_b)] {
+            setup
+                .validate()
+                .map_err(|error| format!("{}: {side}: {error}", self.name))?;
+        }
+        Ok(())
+    }
+}
+
+impl PlayerSetup {
+    fn validate(&self) -> Result<(), String> {
+        for (index, plant) in self.plants.iter().enumerate() {
+            check_shape(&plant.shape).map_err(|error| format!("plant {}: {error}", index + 1))?;
+        }
+        for (index, shape) in self.buffer.iter().enumerate() {
+            check_shape(shape).map_err(|error| format!("buffered shape {}: {error}", index + 1))?;
+        }
+        for (index, active) in self.active.iter().enumerate() {
+            check_shape(&active.shape)
+                .map_err(|error| format!("active shape {}: {error}", index + 1))?;
+            let pos = active.pos;
+            if !pos.is_valid() {
+                return Err(format!(
+                    "active shape {} is outside of the board at depth {}, row {}",
+                    index + 1,
+                    pos.depth,
+                    pos.row
+                ));
+            }
+            if self.active[..index].iter().any(|other| other.pos == pos) {
+                return Err(format!(
+                    "several active shapes at depth {}, row {}",
+                    pos.depth, pos.row
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn check_shape(shape: &Shape) -> Result<(), String> {
+    if shape.0.is_empty() {
+        Err("the shape is empty".to_owned())
+    } else if !shape.is_connected() {
+        Err("the shape is not connected".to_owned())
+    } else {
+        Ok(())
+    }
+}
+
+impl Model {
+    pub fn from_scenario(scenario: &Scenario, opponent: Opponent) -> Self {
+        let mut model = Self::with_mode(TurnMode::Alternating, opponent);
+        let mut id_gen = IdGenerator::new();
+        model.player_a = Player::from_setup(&scenario.player_a, &mut id_gen);
+        model.player_b = Player::from_setup(&scenario.player_b, &mut id_gen);
+        model.player_a_turn = scenario.player_a_turn;
+        model.id_gen = id_gen;
+        model
+    }
+}
+
+impl Player {
+    fn from_setup(setup: &PlayerSetup, id_gen: &mut IdGenerator) -> Self {
+        let mut plants = Collection::new();
+        for plant in &setup.plants {
+            plants.insert(Plant::new(
+                id_gen.next(),
+                plant.shape.clone(),
+                plant.cooldown,
+                plant.time_left,
+            ));
+        }
+        let mut shape_buffer = ShapeBuffer::new();
+        for shape in &setup.buffer {
+            shape_buffer
+                .0
+                .insert(AliveShape::new(id_gen.next(), shape.clone(), None));
+        }
+        let mut active_shapes = ActiveShapes::new();
+        for active in &setup.active {
+            active_shapes.0.insert(AliveShape::new(
+                id_gen.next(),
+                active.shape.clone(),
+                Some(active.pos),
+            ));
+        }
+        Self {
+            shape_buffer,
+            shape_farm: ShapeFarm { plants },
+            active_shapes,
+        }
+    }
+}
+
+impl Goal {
+    /// Checks the goal given the turn on which the scenario started
+    pub fn status(&self, model: &Model, start_turn: Turns) -> GoalStatus {
+        // Each turn of player A is followed by a turn of the opponent
+        let turns_played = (model.turn - start_turn) / 2;
+        match *self {
+            Goal::DestroyPlants { turns } => match model.winner() {
+                Some(true) => GoalStatus::Completed,
+                Some(false) => GoalStatus::Failed,
+                None if turns_played >= turns => GoalStatus::Failed,
+                None => GoalStatus::InProgress,
+            },
+            Goal::Survive { turns } => match model.winner() {
+                Some(false) => GoalStatus::Failed,
+                Some(true) => GoalStatus::Completed,
+                None if turns_played >= turns => GoalStatus::Completed,
+                None => GoalStatus::InProgress,
+            },
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use super::*;
+
+/// The part of the model that one of the players is allowed to see
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayerView {
+    /// The side whose information is hidden, `None` when everything is visible
+    pub hidden: Option<bool>,
+    pub model: Model,
+}
+
+impl PlayerView {
+    /// Whether the information about the given side is hidden
+    pub fn is_hidden(&self, player_a: bool) -> bool {
+        self.hidden == Some(player_a)
+    }
+
+    /// The side the view belongs to, `None` for spectators
+    pub fn viewer(&self) -> Option<bool> {
+        self.hidden.map(|hidden_a| !hidden_a)
+    }
+}
+
+impl Model {
+    /// Projects the model for the viewer, hiding the opponent's buffer contents,
+    /// plant progress and submitted actions.
+    /// With no viewer (spectators) everything is visible.
+    pub fn view(&self, viewer: Option<bool>) -> PlayerView {
+        let mut model = self.clone();
+        let hidden = viewer.map(|player_a| !player_a);
+        if let Some(hidden_a) = hidden {
+            let (player, submitted) = if hidden_a {
+                (&mut model.player_a, &mut model.submitted_a)
+            } else {
+                (&mut model.player_b, &mut model.submitted_b)
+            };
+            for shape in &mut player.shape_buffer.0 {
+                shape.shape = Shape(vec![]);
+            }
+            for plant in &mut player.shape_farm.plants {
+                plant.time_left = plant.cooldown;
+            }
+            if let Some(actions) = submitted {
+                actions.clear();
+            }
+        }
+        PlayerView { hidden, model }
+    }
+}
+
+/// Changes between two views of the same viewer, the players that did not change are left out
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ViewUpdate {
+    id_gen: IdGenerator,
+    pub player_a: Option<Player>,
+    pub player_b: Option<Player>,
+    pub player_a_turn: bool,
+    pub turn: Turns,
+    pub submitted_a: Option<Vec<PlayerAction>>,
+    pub submitted_b: Option<Vec<PlayerAction>>,
+    pub resolved: Vec<(bool, PlayerAction)>,
+}
+
+impl ViewUpdate {
+    pub fn new(old: &PlayerView, new: &PlayerView) -> Self {
+        let changed = |old: &Player, new: &Player| (!same(old, new)).then(|| new.clone());
+        let model = &new.model;
+        Self {
+            id_gen: model.id_gen.clone(),
+            player_a: changed(&old.model.player_a, &model.player_a),
+            player_b: changed(&old.model.player_b, &model.player_b),
+            player_a_turn: model.player_a_turn,
+            turn: model.turn,
+            submitted_a: model.submitted_a.clone(),
+            submitted_b: model.submitted_b.clone(),
+            resolved: model.resolved.clone(),
+        }
+    }
+
+    /// Brings the older view the update was made from up to date
+    pub fn apply(self, model: &mut Model) {
+        model.id_gen = self.id_gen;
+        if let Some(player) = self.player_a {
+            model.player_a = player;
+        }
+        if let Some(player) = self.player_b {
+            model.player_b = player;
+        }
+        model.player_a_turn = self.player_a_turn;
+        model.turn = self.turn;
+        model.submitted_a = self.submitted_a;
+        model.submitted_b = self.submitted_b;
+        model.resolved = self.resolved;
+    }
+}
+
+/// Whether the values serialize the same way, a false negative only makes the update larger
+fn same<T: Serialize>(a: &T, b: &T) -> bool {
+    match (serde_json::to_value(a), serde_json::to_value(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
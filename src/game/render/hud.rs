@@ -0,0 +1,159 @@
+use super::*;
+
+impl Render {
+    /// Draws the turn information, plant timers, shape sizes and the End Turn button
+    pub(super) fn draw_hud(&self, view: &PlayerView, framebuffer: &mut ugli::Framebuffer) {
+        let model = &view.model;
+        let size = self.camera.fov * 0.03;
+
+        // Turn owner and number
+        let (turn, color) = match model.turn_mode {
+            TurnMode::Alternating if model.player_a_turn => ("Blue's turn", Color::BLUE),
+            TurnMode::Alternating => ("Red's turn", Color::RED),
+            TurnMode::Simultaneous => ("Both players plan their moves", Color::WHITE),
+        };
+        let top = self.camera.fov * 0.5 - size * 5.0;
+        self.draw_text(turn, vec2(0.0, top), size * 1.2, color, framebuffer);
+        self.draw_text(
+            &format!("Turn {}", model.displayed_turn()),
+            vec2(0.0, top - size * 1.5),
+            size,
+            Color::GRAY,
+            framebuffer,
+        );
+
+        // Plant timers
+        for player_a in [true, false] {
+            let hidden = view.is_hidden(player_a);
+            for plant in &model.player(player_a).shape_farm.plants {
+                let (pos, scale) = match (self.positions.get(plant.id), self.scales.get(plant.id)) {
+                    (Some(pos), Some(scale)) => (pos.map(|x| x.as_f32()), scale.as_f32()),
+                    _ => continue,
+                };
+                let text = if hidden {
+                    format!("?/{}", plant.cooldown)
+                } else {
+                    format!("{}/{}", plant.time_left, plant.cooldown)
+                };
+                self.draw_text(
+                    &text,
+                    pos - vec2(0.0, scale + size),
+                    size * 0.8,
+                    Color::WHITE,
+                    framebuffer,
+                );
+            }
+        }
+
+        // Shape sizes
+        for player_a in [true, false] {
+            let player = model.player(player_a);
+            for shape in player.shape_buffer.0.iter().chain(&player.active_shapes.0) {
+                if self.dragging == Some(shape.id) {
+                    continue;
+                }
+                let pos = match self.positions.get(shape.id) {
+                    Some(pos) => pos.map(|x| x.as_f32()),
+                    None => continue,
+                };
+                let text = if shape.shape.0.is_empty() {
+                    "?".to_owned()
+                } else {
+                    shape.shape.0.len().to_string()
+                };
+                let bottom = shape
+                    .shape
+                    .0
+                    .iter()
+                    .map(|cell| cell.to_cartesian().y.as_f32())
+                    .fold(0.0, f32::min);
+                self.draw_text(
+                    &text,
+                    pos + vec2(0.0, bottom - size * 1.2),
+                    size * 0.8,
+                    Color::WHITE,
+                    framebuffer,
+                );
+            }
+        }
+
+        // Attack preview next to the target
+        if let Some((target_id, preview, selected)) = self.attack_preview {
+            if let Some(pos) = self.positions.get(target_id) {
+                let pos = pos.map(|x| x.as_f32()) + vec2(0.0, 1.5);
+                let outcome = |survives: bool| if survives { "survives" } else { "destroyed" };
+                let mut lines = vec![
+                    format!(
+                        "Attacker: -{} ({})",
+                        preview.weapon_loss,
+                        outcome(preview.weapon_survives)
+                    ),
+                    format!(
+                        "Target: -{} ({})",
+                        preview.target_loss,
+                        outcome(preview.target_survives)
+                    ),
+                ];
+                if preview.plant_delay > 0 {
+                    lines.push(format!("Harvest delayed by {}", preview.plant_delay));
+                }
+                if selected {
+                    lines.push("Click again to attack".to_owned());
+                }
+                for (index, line) in lines.iter().enumerate() {
+                    self.draw_text(
+                        line,
+                        pos + vec2(0.0, (lines.len() - index) as f32 * size),
+                        size * 0.8,
+                        Color::YELLOW,
+                        framebuffer,
+                    );
+                }
+            }
+        }
+
+        if let Some(hint) = &self.controller_hint {
+            self.draw_text(
+                hint,
+                vec2(0.0, -self.camera.fov * 0.5 + size),
+                size * 0.8,
+                Color::YELLOW,
+                framebuffer,
+            );
+        }
+
+        // End Turn button
+        let button = self.layout.end_turn_button;
+        draw_2d::Quad::new(button.0, Color::rgb(0.2, 0.2, 0.2)).draw_2d(
+            &self.geng,
+            framebuffer,
+            &self.camera,
+        );
+        self.draw_text(
+            "End Turn",
+            button.0.center() - vec2(0.0, size * 0.4),
+            size,
+            Color::WHITE,
+            framebuffer,
+        );
+    }
+
+    fn draw_text(
+        &self,
+        text: &str,
+        pos: Vec2<f32>,
+        size: f32,
+        color: Color<f32>,
+        framebuffer: &mut ugli::Framebuffer,
+    ) {
+        self.assets.font.draw(
+            framebuffer,
+            &self.camera,
+            text,
+            pos,
+            geng::TextAlign::CENTER,
+            size,
+            color,
+        );
+    }
+}
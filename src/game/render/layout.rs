@@ -14,6 +14,16 @@ pub struct Layout {
     pub shape_buffer_b: Area,
     pub active_shapes_b: Area,
     pub shape_farm_b: Area,
+    /// Clicking it ends the turn
+    pub end_turn_button: Area,
+}
+
+/// Areas belonging to one of the players
+#[derive(Debug, Clone, Copy)]
+pub struct PlayerLayout {
+    pub shape_buffer: Area,
+    pub active_shapes: Area,
+    pub shape_farm: Area,
 }
 
 impl RelativeLayout {
@@ -31,6 +41,7 @@ impl RelativeLayout {
         let active_shapes_a = Area::new((0.5 - DANGER_ZONE, 0.3), (0.45, 0.7));
         let shape_farm_a = Area::new((0.05, 0.1), (0.2, 0.25));
         Self(Layout {
+            end_turn_button: Area::new((0.42, 0.08), (0.58, 0.18)),
             shape_buffer_b: flip(&shape_buffer_a),
             active_shapes_b: flip(&active_shapes_a),
             shape_farm_b: flip(&shape_farm_a),
@@ -50,8 +61,51 @@ impl RelativeLayout {
             shape_buffer_b: adapt(layout.shape_buffer_b),
             active_shapes_b: adapt(layout.active_shapes_b),
             shape_farm_b: adapt(layout.shape_farm_b),
+            end_turn_button: adapt(layout.end_turn_button),
+        }
+    }
+}
+
+impl Layout {
+    pub fn player(&self, player_a: bool) -> PlayerLayout {
+        if player_a {
+            PlayerLayout {
+                shape_buffer: self.shape_buffer_a,
+                active_shapes: self.active_shapes_a,
+                shape_farm: self.shape_farm_a,
+            }
+        } else {
+            PlayerLayout {
+                shape_buffer: self.shape_buffer_b,
+                active_shapes: self.active_shapes_b,
+                shape_farm: self.shape_farm_b,
+            }
         }
     }
+
+    /// Returns the center of the board cell in the active zone of the player
+    pub fn board_cell(&self, player_a: bool, pos: BoardPos) -> Vec2<f32> {
+        let area = self.player(player_a).active_shapes;
+        let depth = (pos.depth as f32 + 0.5) / BOARD_DEPTH as f32;
+        let x = if player_a { 1.0 - depth } else { depth };
+        area.point(x, (pos.row as f32 + 0.5) / BOARD_ROWS as f32)
+    }
+
+    /// Returns the board cell of the player at the given point
+    pub fn board_pos_at(&self, player_a: bool, point: Vec2<f32>) -> Option<BoardPos> {
+        let area = self.player(player_a).active_shapes;
+        if !area.contains(point) {
+            return None;
+        }
+        let x = (point.x - area.0.x_min) / area.0.width();
+        let y = (point.y - area.0.y_min) / area.0.height();
+        let depth = if player_a { 1.0 - x } else { x };
+        let pos = BoardPos {
+            depth: ((depth * BOARD_DEPTH as f32) as i64).min(BOARD_DEPTH - 1),
+            row: ((y * BOARD_ROWS as f32) as i64).min(BOARD_ROWS - 1),
+        };
+        Some(pos)
+    }
 }
 
 impl Area {
@@ -2,12 +2,17 @@ use super::*;
 
 use model::*;
 
+mod hud;
 mod layout;
+pub mod packing;
 
-use layout::*;
+pub use layout::*;
 
 pub type Coord = R32;
 
+/// Drawn in place of the shapes whose contents are hidden
+const UNKNOWN_SHAPE: [TriPos; 1] = [TriPos { x: 0, y: 0 }];
+
 pub struct Storage<T>(HashMap<Id, T>);
 
 impl<T> Storage<T> {
@@ -36,6 +41,15 @@ impl<T> Storage<T> {
     }
 }
 
+/// A cell where the dragged triangle would be attached on release
+#[derive(Debug, Clone, Copy)]
+pub struct AttachGhost {
+    pub target: Id,
+    pub pos: TriPos,
+    /// Invalid ghosts mark cells that are already occupied
+    pub valid: bool,
+}
+
 pub struct Render {
     geng: Geng,
     assets: Rc<Assets>,
@@ -43,6 +57,27 @@ pub struct Render {
     pub layout: Layout,
     pub positions: Storage<Vec2<Coord>>,
     pub scales: Storage<Coord>,
+    /// Shapes the player has put somewhere in the buffer by hand,
+    /// they stay where they were dropped instead of being packed
+    pub pinned: HashSet<Id>,
+    /// The shape currently dragged by the player, it does not snap to the board
+    pub dragging: Option<Id>,
+    /// Rotation of the dragged shape in steps of 60 degrees
+    pub drag_rotation: i64,
+    /// Describes what the controller is doing, `None` when it is idle
+    pub controller_hint: Option<String>,
+    /// The shape used in the current interaction, i.e. the attacking weapon
+    pub selected: Option<Id>,
+    /// Board cell chosen with the keyboard
+    pub board_cursor: Option<BoardPos>,
+    /// Where the dragged triangle is going to be attached
+    pub attach_ghost: Option<AttachGhost>,
+    /// The cell being detached and the point it is dragged to
+    pub detached_cell: Option<(TriPos, Vec2<Coord>)>,
+    /// Predicted outcome of attacking the target and whether it awaits confirmation
+    pub attack_preview: Option<(Id, AttackPreview, bool)>,
+    /// Weapons and targets of the attacks planned for the end of a simultaneous turn
+    pub planned_attacks: Vec<(Id, Id)>,
     camera: Camera2d,
     framebuffer_size: Vec2<f32>,
 }
@@ -62,6 +97,16 @@ impl Render {
             layout: RelativeLayout::new().adapt(AABB::ZERO.extend_uniform(1.0)),
             positions: Storage::new(),
             scales: Storage::new(),
+            pinned: HashSet::new(),
+            dragging: None,
+            drag_rotation: 0,
+            controller_hint: None,
+            selected: None,
+            board_cursor: None,
+            attach_ghost: None,
+            detached_cell: None,
+            attack_preview: None,
+            planned_attacks: Vec::new(),
             camera: Camera2d {
                 center: vec2(0.0, 0.0),
                 rotation: 0.0,
@@ -71,7 +116,78 @@ impl Render {
         }
     }
 
-    pub fn draw(&mut self, model: &Model, framebuffer: &mut ugli::Framebuffer) {
+    /// Packs the shapes into their zones, around the shapes on the board
+    /// and the ones placed by the player
+    fn arrange(&mut self, model: &Model) {
+        let buffered: HashSet<Id> = [&model.player_a, &model.player_b]
+            .into_iter()
+            .flat_map(|player| player.shape_buffer.0.iter().map(|shape| shape.id))
+            .collect();
+        // Shapes that leave the buffer get packed again when they come back
+        self.pinned.retain(|id| buffered.contains(id));
+
+        for (player, player_a) in [(&model.player_a, true), (&model.player_b, false)] {
+            let zones = self.layout.player(player_a);
+            let buffer = player
+                .shape_buffer
+                .0
+                .iter()
+                .map(|shape| packing::Item {
+                    id: shape.id,
+                    bounds: packing::shape_bounds(shape_cells(&shape.shape), 1.0),
+                    fixed: self
+                        .positions
+                        .get(shape.id)
+                        .filter(|_| {
+                            self.pinned.contains(&shape.id) && self.dragging != Some(shape.id)
+                        })
+                        .map(|pos| pos.map(|x| x.as_f32())),
+                })
+                .collect::<Vec<_>>();
+            let active = player
+                .active_shapes
+                .0
+                .iter()
+                .map(|shape| packing::Item {
+                    id: shape.id,
+                    bounds: packing::shape_bounds(shape_cells(&shape.shape), 1.0),
+                    fixed: shape
+                        .board_pos
+                        .map(|pos| self.layout.board_cell(player_a, pos)),
+                })
+                .collect::<Vec<_>>();
+            let farm = player
+                .shape_farm
+                .plants
+                .iter()
+                .map(|plant| packing::Item {
+                    id: plant.id,
+                    bounds: packing::shape_bounds(
+                        shape_cells(&plant.shape),
+                        plant_scale(&plant.shape).as_f32(),
+                    ),
+                    fixed: None,
+                })
+                .collect::<Vec<_>>();
+            for (area, mut items) in [
+                (zones.shape_buffer, buffer),
+                (zones.active_shapes, active),
+                (zones.shape_farm, farm),
+            ] {
+                // Collections iterate in hash order, the layout must not depend on it
+                items.sort_by_key(|item| item.id);
+                for (id, pos) in packing::pack(area.0, &items) {
+                    // The dragged shape keeps its place in the zone until it is dropped
+                    if self.dragging != Some(id) {
+                        self.positions.insert(id, pos.map(r32));
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn draw(&mut self, view: &PlayerView, framebuffer: &mut ugli::Framebuffer) {
+        let model = &view.model;
         let framebuffer_size = framebuffer.size().map(|x| x as f32);
         if self.framebuffer_size != framebuffer_size {
             self.positions
@@ -88,8 +204,31 @@ impl Render {
         );
 
         self.layout = self.relative_layout.adapt(bounds);
+        self.arrange(model);
         let layout = &self.layout;
 
+        // The board cells that movement and the attack range are measured in
+        let grid_width = 0.003 * bounds.width();
+        let grid_color = Color::rgb(0.15, 0.15, 0.15);
+        for player_a in [true, false] {
+            let area = layout.player(player_a).active_shapes;
+            let columns = (1..BOARD_DEPTH).map(|depth| {
+                let x = depth as f32 / BOARD_DEPTH as f32;
+                (area.point(x, 0.0), area.point(x, 1.0))
+            });
+            let rows = (1..BOARD_ROWS).map(|row| {
+                let y = row as f32 / BOARD_ROWS as f32;
+                (area.point(0.0, y), area.point(1.0, y))
+            });
+            for (start, end) in columns.chain(rows) {
+                draw_2d::Segment::new(Segment::new(start, end), grid_width, grid_color).draw_2d(
+                    &self.geng,
+                    framebuffer,
+                    &self.camera,
+                );
+            }
+        }
+
         let active_shapes = layout.active_shapes_a.join(&layout.active_shapes_b);
         draw_2d::Segment::new(
             Segment::new(active_shapes.point(0.0, 0.0), active_shapes.point(0.0, 1.0)),
@@ -110,40 +249,47 @@ impl Render {
         )
         .draw_2d(&self.geng, framebuffer, &self.camera);
 
-        let plants_a = model
-            .player_a
-            .shape_farm
-            .plants
-            .iter()
-            .map(|plant| (plant, Color::BLUE, layout.shape_farm_a.0));
-        let plants_b = model
-            .player_b
-            .shape_farm
-            .plants
-            .iter()
-            .map(|plant| (plant, Color::RED, layout.shape_farm_b.0));
-        let plants = plants_a.chain(plants_b).map(|(plant, color, layout)| {
-            let random_pos = random_point_in(layout).map(r32);
-            let position = *self.positions.get_or_default(plant.id, random_pos);
-            let bounding_box =
-                AABB::points_bounding_box(plant.shape.0.iter().map(|pos| pos.to_cartesian())); // TODO: avoid panic when shape has no points
-            let scale = r32(1.0)
-                / bounding_box
-                    .width()
-                    .max(bounding_box.height())
-                    .max(r32(1.0));
-            self.scales.insert(plant.id, scale);
-            let draw_count = (((1.0 - plant.time_left as f32 / plant.cooldown as f32)
-                * plant.shape.0.len() as f32)
-                .ceil() as usize)
-                .max(1);
+        let plants_a = model.player_a.shape_farm.plants.iter().map(|plant| {
             (
-                position.map(|x| x.as_f32()),
-                plant.shape.0.iter().take(draw_count),
-                scale.as_f32(),
-                color,
+                plant,
+                Color::BLUE,
+                layout.shape_farm_a.0,
+                view.is_hidden(true),
+            )
+        });
+        let plants_b = model.player_b.shape_farm.plants.iter().map(|plant| {
+            (
+                plant,
+                Color::RED,
+                layout.shape_farm_b.0,
+                view.is_hidden(false),
             )
         });
+        let plants = plants_a
+            .chain(plants_b)
+            .map(|(plant, color, layout, hidden)| {
+                let position = *self
+                    .positions
+                    .get_or_default(plant.id, layout.center().map(r32));
+                let scale = plant_scale(&plant.shape);
+                self.scales.insert(plant.id, scale);
+                // The progress of the hidden plants is unknown, so they are drawn dimmed
+                let (draw_count, color) = if hidden {
+                    (plant.shape.0.len(), desaturate(color))
+                } else {
+                    let draw_count = (((1.0 - plant.time_left as f32 / plant.cooldown as f32)
+                        * plant.shape.0.len() as f32)
+                        .ceil() as usize)
+                        .max(1);
+                    (draw_count, color)
+                };
+                (
+                    position.map(|x| x.as_f32()),
+                    plant.shape.0.iter().take(draw_count),
+                    scale.as_f32(),
+                    color,
+                )
+            });
 
         draw_shapes(plants, &self.camera, &self.geng, framebuffer);
 
@@ -175,13 +321,162 @@ impl Render {
             .chain(buffer_b)
             .chain(active_a)
             .chain(active_b)
+            .filter(|(shape, _, _)| self.dragging != Some(shape.id))
             .map(|(shape, color, layout)| {
-                let random_pos = random_point_in(layout).map(r32);
-                let position = *self.positions.get_or_default(shape.id, random_pos);
-                (position.map(|x| x.as_f32()), &shape.shape.0, 1.0, color)
+                let position = *self
+                    .positions
+                    .get_or_default(shape.id, layout.center().map(r32));
+                let color = if shape.exhausted {
+                    desaturate(color)
+                } else {
+                    color
+                };
+                (
+                    position.map(|x| x.as_f32()),
+                    shape_cells(&shape.shape),
+                    1.0,
+                    color,
+                )
             });
 
         draw_shapes(shapes, &self.camera, &self.geng, framebuffer);
+
+        // Highlight the shape or plant used in the current interaction
+        let selected = self.selected.and_then(|id| {
+            let player = model.player(model.perspective());
+            let (shape, scale) = match player
+                .active_shapes
+                .0
+                .get(&id)
+                .or_else(|| player.shape_buffer.0.get(&id))
+            {
+                Some(shape) => (&shape.shape, r32(1.0)),
+                None => (
+                    &player.shape_farm.plants.get(&id)?.shape,
+                    *self.scales.get(id)?,
+                ),
+            };
+            Some((*self.positions.get(id)?, shape, scale))
+        });
+        if let Some((pos, shape, scale)) = selected {
+            draw_shape(
+                pos.map(|x| x.as_f32()),
+                &shape.0,
+                scale.as_f32(),
+                Color::rgba(1.0, 1.0, 0.0, 0.5),
+                &self.camera,
+                &self.geng,
+                framebuffer,
+            );
+        }
+
+        if let Some(pos) = self.board_cursor {
+            let center = layout.board_cell(model.perspective(), pos);
+            let area = layout.player(model.perspective()).active_shapes.0;
+            let size = vec2(
+                area.width() / BOARD_DEPTH as f32,
+                area.height() / BOARD_ROWS as f32,
+            );
+            draw_2d::Quad::new(
+                AABB::point(center).extend_symmetric(size / 2.0),
+                Color::rgba(1.0, 1.0, 0.0, 0.3),
+            )
+            .draw_2d(&self.geng, framebuffer, &self.camera);
+        }
+
+        if let Some(ghost) = self.attach_ghost {
+            if let Some(pos) = self.positions.get(ghost.target) {
+                let color = if ghost.valid {
+                    Color::rgba(1.0, 1.0, 1.0, 0.4)
+                } else {
+                    Color::rgba(1.0, 0.0, 0.0, 0.6)
+                };
+                draw_shape(
+                    pos.map(|x| x.as_f32()),
+                    &[ghost.pos],
+                    1.0,
+                    color,
+                    &self.camera,
+                    &self.geng,
+                    framebuffer,
+                );
+            }
+        }
+
+        // The detached cell follows the cursor
+        if let Some((cell, pos)) = self.detached_cell {
+            draw_shape(
+                (pos - cell.to_cartesian()).map(|x| x.as_f32()),
+                &[cell],
+                1.0,
+                Color::rgba(1.0, 1.0, 1.0, 0.6),
+                &self.camera,
+                &self.geng,
+                framebuffer,
+            );
+        }
+
+        // Draw the dragged shape on top with its current rotation
+        let dragged = self.dragging.and_then(|id| {
+            let player_a = model.perspective();
+            let player = model.player(player_a);
+            let color = if player_a { Color::BLUE } else { Color::RED };
+            player
+                .shape_buffer
+                .0
+                .get(&id)
+                .map(|shape| (shape, Color::GRAY))
+                .or_else(|| player.active_shapes.0.get(&id).map(|shape| (shape, color)))
+        });
+        if let Some((shape, color)) = dragged {
+            if let Some(pos) = self.positions.get(shape.id) {
+                let cells = shape
+                    .shape
+                    .transformed(self.drag_rotation, TriPos { x: 0, y: 0 });
+                let color = if shape.exhausted {
+                    desaturate(color)
+                } else {
+                    color
+                };
+                draw_shape(
+                    pos.map(|x| x.as_f32()),
+                    &cells.0,
+                    1.0,
+                    color,
+                    &self.camera,
+                    &self.geng,
+                    framebuffer,
+                );
+            }
+        }
+
+        for &(weapon, target) in &self.planned_attacks {
+            if let (Some(from), Some(to)) = (self.positions.get(weapon), self.positions.get(target))
+            {
+                draw_2d::Segment::new(
+                    Segment::new(from.map(|x| x.as_f32()), to.map(|x| x.as_f32())),
+                    0.1,
+                    Color::rgba(1.0, 1.0, 0.0, 0.6),
+                )
+                .draw_2d(&self.geng, framebuffer, &self.camera);
+            }
+        }
+
+        self.draw_hud(view, framebuffer);
+    }
+}
+
+impl Render {
+    /// Draws a line of text at the top of the screen
+    pub fn draw_message(&self, text: &str, framebuffer: &mut ugli::Framebuffer) {
+        let size = self.camera.fov * 0.04;
+        self.draw_text(
+            text,
+            vec2(0.0, self.camera.fov * 0.5 - size * 2.0),
+            size,
+            Color::WHITE,
+            framebuffer,
+        );
     }
 }
 
@@ -203,11 +498,31 @@ pub fn draw_shapes<'a>(
     }
 }
 
-pub fn random_point_in(aabb: AABB<f32>) -> Vec2<f32> {
-    vec2(
-        global_rng().gen_range(aabb.x_min..=aabb.x_max),
-        global_rng().gen_range(aabb.y_min..=aabb.y_max),
-    )
+/// Blends the color towards gray to indicate an unavailable shape
+pub fn desaturate(color: Color<f32>) -> Color<f32> {
+    let gray = (color.r + color.g + color.b) / 3.0;
+    let blend = |x: f32| (x + gray) / 2.0 * 0.6;
+    Color::rgba(blend(color.r), blend(color.g), blend(color.b), color.a)
+}
+
+/// The cells to draw for the shape, hidden shapes are drawn as a single triangle
+fn shape_cells(shape: &Shape) -> &[TriPos] {
+    if shape.0.is_empty() {
+        &UNKNOWN_SHAPE
+    } else {
+        &shape.0
+    }
+}
+
+/// Plants are scaled down to fit into a single cell
+fn plant_scale(shape: &Shape) -> Coord {
+    let bounding_box =
+        AABB::points_bounding_box(shape_cells(shape).iter().map(|pos| pos.to_cartesian()));
+    r32(1.0)
+        / bounding_box
+            .width()
+            .max(bounding_box.height())
+            .max(r32(1.0))
 }
 
 pub fn draw_shape<'a>(
@@ -0,0 +1,103 @@
+use super::*;
+
+/// Space left between the packed shapes
+const MARGIN: f32 = 0.3;
+
+/// A shape to be placed inside of a zone
+pub struct Item {
+    pub id: Id,
+    /// Bounding box of the shape relative to its position
+    pub bounds: AABB<f32>,
+    /// The shapes that are not fixed are packed around this position
+    pub fixed: Option<Vec2<f32>>,
+}
+
+/// Arranges the shapes in rows starting from the top left corner of the area,
+/// so that they overlap neither each other nor the fixed shapes.
+/// Shapes that do not fit are spread over the bottom of the area on top of the others,
+/// so that everything stays inside of it.
+/// The result only depends on the order of the items, not on the previous positions.
+/// Returns the positions of the shapes that are not fixed.
+pub fn pack(area: AABB<f32>, items: &[Item]) -> Vec<(Id, Vec2<f32>)> {
+    let mut placed: Vec<AABB<f32>> = items
+        .iter()
+        .filter_map(|item| item.fixed.map(|pos| item.bounds.translate(pos)))
+        .collect();
+    let mut positions = Vec::new();
+    let mut cursor = vec2(area.x_min, area.y_max);
+    let mut row_height: f32 = 0.0;
+    let mut overflow = Vec::new();
+    for item in items.iter().filter(|item| item.fixed.is_none()) {
+        let size = item.bounds.size();
+        let candidate = loop {
+            // Shapes wider than the area get a row of their own
+            if cursor.x + size.x > area.x_max && cursor.x > area.x_min {
+                cursor = vec2(area.x_min, cursor.y - row_height - MARGIN);
+                row_height = 0.0;
+            }
+            let candidate = AABB::point(vec2(cursor.x, cursor.y - size.y)).extend_positive(size);
+            match placed.iter().find(|other| overlaps(&candidate, other)) {
+                Some(other) => cursor.x = other.x_max + MARGIN,
+                None => break candidate,
+            }
+        };
+        if candidate.y_min < area.y_min {
+            overflow.push(item);
+            continue;
+        }
+        placed.push(candidate);
+        positions.push((item.id, candidate.bottom_left() - item.bounds.bottom_left()));
+        cursor.x += size.x + MARGIN;
+        row_height = row_height.max(size.y);
+    }
+
+    let step = if overflow.len() > 1 {
+        let widest = overflow
+            .iter()
+            .map(|item| item.bounds.width())
+            .fold(0.0, f32::max);
+        (area.width() - widest).max(0.0) / (overflow.len() - 1) as f32
+    } else {
+        0.0
+    };
+    for (index, item) in overflow.into_iter().enumerate() {
+        let corner = vec2(area.x_min + step * index as f32, area.y_min);
+        let candidate = AABB::point(corner).extend_positive(item.bounds.size());
+        positions.push((
+            item.id,
+            clamp_inside(area, candidate).bottom_left() - item.bounds.bottom_left(),
+        ));
+    }
+    positions
+}
+
+/// Moves the box inside of the area, keeping its top left corner inside if it is too large
+fn clamp_inside(area: AABB<f32>, bounds: AABB<f32>) -> AABB<f32> {
+    let x = bounds
+        .x_min
+        .min(area.x_max - bounds.width())
+        .max(area.x_min);
+    let y = bounds
+        .y_max
+        .max(area.y_min + bounds.height())
+        .min(area.y_max);
+    bounds.translate(vec2(x - bounds.x_min, y - bounds.y_max))
+}
+
+/// Returns the bounding box of the cells drawn with the scale
+pub fn shape_bounds(cells: &[TriPos], scale: f32) -> AABB<f32> {
+    AABB::points_bounding_box(
+        cells
+            .iter()
+            .flat_map(|pos| pos.to_vertices())
+            .map(|vertex| vertex.map(|x| x.as_f32() * scale)),
+    )
+}
+
+/// Checks whether the boxes overlap, keeping a margin between them
+fn overlaps(a: &AABB<f32>, b: &AABB<f32>) -> bool {
+    a.x_min < b.x_max + MARGIN
+        && b.x_min < a.x_max + MARGIN
+        && a.y_min < b.y_max + MARGIN
+        && b.y_min < a.y_max + MARGIN
+}
@@ -0,0 +1,12 @@
+use geng::prelude::*;
+use geng::{Camera2d, Draw2d};
+
+mod assets;
+pub mod game;
+pub mod menu;
+pub mod net;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod server;
+pub mod simulation;
+
+pub use assets::*;
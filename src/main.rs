@@ -1,30 +1,86 @@
 use geng::prelude::*;
-use geng::{Camera2d, Draw2d};
 
-mod assets;
-mod game;
-
-use assets::*;
+use rusty_jam_2::*;
 
 fn main() {
     logger::init().unwrap();
     geng::setup_panic_handler();
 
+    let args: Vec<String> = std::env::args().collect();
+    let arg_value = |name: &str| {
+        args.iter()
+            .position(|arg| arg == name)
+            .and_then(|index| args.get(index + 1))
+            .cloned()
+    };
+    let turn_mode = if args.iter().any(|arg| arg == "--simultaneous") {
+        game::TurnMode::Simultaneous
+    } else {
+        game::TurnMode::Alternating
+    };
+    // The menu is skipped when the mode is given on the command line
+    let opponent = if args.iter().any(|arg| arg == "--hotseat") {
+        Some(game::Opponent::Human)
+    } else if args.iter().any(|arg| arg == "--ai") {
+        Some(game::Opponent::Ai)
+    } else {
+        None
+    };
+    let server_addr = arg_value("--connect");
+    let spectate = args.iter().any(|arg| arg == "--spectate");
+    let puzzles = args.iter().any(|arg| arg == "--puzzles");
+    let campaign = args.iter().any(|arg| arg == "--campaign");
+
     let geng = Geng::new_with(geng::ContextOptions {
         title: "Untitled Circle Game".to_owned(),
         ..Default::default()
     });
     let assets = <Assets as geng::LoadAsset>::load(&geng, &static_path());
 
-    geng::run(
-        &geng,
-        geng::LoadingScreen::new(&geng, geng::EmptyLoadingScreen, assets, {
-            let geng = geng.clone();
-            move |assets| {
-                let assets = assets.unwrap();
-                let assets = Rc::new(assets);
-                game::Game::new(&geng, &assets)
-            }
-        }),
-    )
+    match server_addr {
+        Some(addr) => {
+            let connection = geng::net::client::connect(&format!("ws://{addr}"));
+            geng::run(
+                &geng,
+                geng::LoadingScreen::new(
+                    &geng,
+                    geng::EmptyLoadingScreen,
+                    future::join(assets, connection),
+                    {
+                        let geng = geng.clone();
+                        move |(assets, connection)| {
+                            let assets = assets.unwrap();
+                            let assets = Rc::new(assets);
+                            let game = game::Game::new_remote(&geng, &assets, connection, spectate);
+                            let mut state_manager = geng::StateManager::new();
+                            state_manager.push(Box::new(menu::MainMenu::new(&geng, &assets)));
+                            state_manager.push(Box::new(menu::Match::new(&geng, game)));
+                            state_manager
+                        }
+                    },
+                ),
+            )
+        }
+        None => geng::run(
+            &geng,
+            geng::LoadingScreen::new(&geng, geng::EmptyLoadingScreen, assets, {
+                let geng = geng.clone();
+                move |assets| {
+                    let assets = assets.unwrap();
+                    let assets = Rc::new(assets);
+                    let mut state_manager = geng::StateManager::new();
+                    state_manager.push(Box::new(menu::MainMenu::new(&geng, &assets)));
+                    if campaign {
+                        state_manager.push(Box::new(menu::CampaignMap::new(&geng, &assets)));
+                    } else if puzzles {
+                        state_manager.push(Box::new(menu::PuzzleSelect::new(&geng, &assets)));
+                    } else if let Some(opponent) = opponent {
+                        let game = game::Game::new(&geng, &assets, turn_mode, opponent);
+                        state_manager.push(Box::new(menu::Match::new(&geng, game)));
+                    }
+                    state_manager
+                }
+            }),
+        ),
+    }
 }
@@ -0,0 +1,204 @@
+use super::*;
+
+use game::input::{Binding, Bindings, InputAction, INPUT_ACTIONS, POINTER_ACTIONS};
+
+/// Lists the bindings of the input actions and lets the player change them
+pub struct BindingsMenu {
+    geng: Geng,
+    camera: Camera2d,
+    bindings: Bindings,
+    selected: usize,
+    /// Whether the next key or mouse button is bound to the selected action
+    waiting: bool,
+    /// Whether the new binding is added to the existing ones instead of replacing them
+    adding: bool,
+    /// Why the last binding was rejected
+    warning: Option<String>,
+    transition: Option<geng::Transition>,
+}
+
+impl BindingsMenu {
+    pub fn new(geng: &Geng) -> Self {
+        Self {
+            geng: geng.clone(),
+            camera: Camera2d {
+                center: vec2(0.0, 0.0),
+                rotation: 0.0,
+                fov: 20.0,
+            },
+            bindings: Bindings::load(),
+            selected: 0,
+            waiting: false,
+            adding: false,
+            warning: None,
+            transition: None,
+        }
+    }
+
+    /// Number of options: the actions, reset and back
+    fn options_count() -> usize {
+        INPUT_ACTIONS.len() + 2
+    }
+
+    fn activate(&mut self, adding: bool) {
+        self.warning = None;
+        match self.selected {
+            index if index < INPUT_ACTIONS.len() => {
+                self.waiting = true;
+                self.adding = adding;
+            }
+            index if index == INPUT_ACTIONS.len() => {
+                self.bindings = Bindings::default();
+                self.bindings.save();
+            }
+            _ => self.transition = Some(geng::Transition::Pop),
+        }
+    }
+
+    fn bind(&mut self, binding: Binding) {
+        self.waiting = false;
+        let action = INPUT_ACTIONS[self.selected];
+        if POINTER_ACTIONS.contains(&action) && matches!(binding, Binding::Key(_)) {
+            self.warning = Some(format!("{} needs a mouse button", action_name(action)));
+            return;
+        }
+        if let Some(other) = self.bindings.conflict(action, binding) {
+            self.warning = Some(format!(
+                "{binding} is already bound to {}",
+                action_name(other)
+            ));
+            return;
+        }
+        let bindings = self.bindings.0.entry(action).or_default();
+        if !self.adding {
+            bindings.clear();
+        }
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+        self.bindings.save();
+    }
+}
+
+fn action_name(action: InputAction) -> &'static str {
+    match action {
+        InputAction::EndTurn => "End turn",
+        InputAction::Select => "Select",
+        InputAction::Detach => "Detach a cell",
+        InputAction::Cancel => "Cancel",
+        InputAction::Rotate => "Rotate",
+        InputAction::Undo => "Undo",
+        InputAction::Pause => "Pause",
+        InputAction::NextZone => "Next zone",
+        InputAction::Up => "Up",
+        InputAction::Down => "Down",
+        InputAction::Left => "Left",
+        InputAction::Right => "Right",
+        InputAction::Confirm => "Confirm",
+        InputAction::Attach => "Attach",
+        InputAction::Upgrade => "Upgrade a plant",
+        InputAction::Move => "Move",
+        InputAction::Deactivate => "Deactivate",
+    }
+}
+
+impl geng::State for BindingsMenu {
+    fn draw(&mut self, framebuffer: &mut ugli::Framebuffer) {
+        ugli::clear(framebuffer, Some(Color::BLACK), None);
+        let font = self.geng.default_font();
+        font.draw(
+            framebuffer,
+            &self.camera,
+            "Controls",
+            vec2(0.0, 7.5),
+            geng::TextAlign::CENTER,
+            1.5,
+            Color::WHITE,
+        );
+        let size = 0.6;
+        let actions = INPUT_ACTIONS.iter().map(|&action| {
+            let bindings = self
+                .bindings
+                .get(action)
+                .iter()
+                .map(|binding| binding.to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("{}: {bindings}", action_name(action))
+        });
+        let options = actions.chain(["Reset to defaults".to_owned(), "Back".to_owned()]);
+        for (index, option) in options.enumerate() {
+            let (option, color) = if index == self.selected && self.waiting {
+                (
+                    "Press a key or a mouse button, Escape to abort".to_owned(),
+                    Color::YELLOW,
+                )
+            } else if index == self.selected {
+                (option, Color::BLUE)
+            } else {
+                (option, Color::WHITE)
+            };
+            font.draw(
+                framebuffer,
+                &self.camera,
+                &option,
+                vec2(0.0, 6.0 - index as f32 * size * 1.35),
+                geng::TextAlign::CENTER,
+                size,
+                color,
+            );
+        }
+        if let Some(warning) = &self.warning {
+            font.draw(
+                framebuffer,
+                &self.camera,
+                warning,
+                vec2(0.0, -8.0),
+                geng::TextAlign::CENTER,
+                0.5,
+                Color::RED,
+            );
+        }
+        let hint = "Up/Down to choose, Enter to rebind, Space to add a binding, Escape to go back";
+        font.draw(
+            framebuffer,
+            &self.camera,
+            hint,
+            vec2(0.0, -9.0),
+            geng::TextAlign::CENTER,
+            0.5,
+            Color::GRAY,
+        );
+    }
+
+    fn handle_event(&mut self, event: geng::Event) {
+        if self.waiting {
+            match event {
+                // Escape aborts the rebind, the defaults still bind it
+                geng::Event::KeyDown {
+                    key: geng::Key::Escape,
+                } => self.waiting = false,
+                geng::Event::KeyDown { key } => self.bind(Binding::Key(key)),
+                geng::Event::MouseDown { button, .. } => self.bind(Binding::Mouse(button)),
+                _ => {}
+            }
+            return;
+        }
+        if let geng::Event::KeyDown { key } = event {
+            match key {
+                geng::Key::Up => self.selected = self.selected.saturating_sub(1),
+                geng::Key::Down => {
+                    self.selected = (self.selected + 1).min(Self::options_count() - 1)
+                }
+                geng::Key::Enter => self.activate(false),
+                geng::Key::Space => self.activate(true),
+                geng::Key::Escape => self.transition = Some(geng::Transition::Pop),
+                _ => {}
+            }
+        }
+    }
+
+    fn transition(&mut self) -> Option<geng::Transition> {
+        self.transition.take()
+    }
+}
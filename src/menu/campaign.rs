@@ -0,0 +1,366 @@
+use super::*;
+use std::cell::Cell;
+
+use game::model::logic::agents;
+use game::model::{GoalStatus, PlantSetup, Scenario, Shape, Turns};
+
+/// Where the campaign progress is saved between runs
+const PROFILE_PATH: &str = "profile.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Campaign {
+    pub levels: Vec<Level>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Level {
+    pub scenario: Scenario,
+    /// Name of the agent controlling the opponent
+    pub agent: String,
+    /// Reward for completing the level
+    #[serde(default)]
+    pub unlock: Option<Unlock>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Unlock {
+    /// An additional plant the player starts every following battle with
+    PlantShape {
+        name: String,
+        shape: Shape,
+        cooldown: Turns,
+    },
+    /// A shape the player starts every following battle with in the buffer
+    StartingShape { name: String, shape: Shape },
+    /// An ability used in every following battle
+    Ability { name: String, ability: Ability },
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum Ability {
+    /// All plants of the player harvest one turn sooner, but not more often than every turn
+    FastGrowth,
+}
+
+/// Campaign progress of the local player
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Profile {
+    /// Number of levels completed, the next level is the one at that index
+    pub completed: usize,
+    pub unlocks: Vec<Unlock>,
+}
+
+impl Profile {
+    pub fn load() -> Self {
+        match std::fs::read_to_string(PROFILE_PATH) {
+            Ok(profile) => serde_json::from_str(&profile).unwrap_or_else(|error| {
+                error!("Failed to parse the profile, starting a new one: {error}");
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn save(&self) {
+        let profile = serde_json::to_string_pretty(self).expect("Failed to serialize the profile");
+        if let Err(error) = std::fs::write(PROFILE_PATH, profile) {
+            error!("Failed to save the profile: {error}");
+        }
+    }
+}
+
+impl Unlock {
+    pub fn name(&self) -> &str {
+        match self {
+            Self::PlantShape { name, .. }
+            | Self::StartingShape { name, .. }
+            | Self::Ability { name, .. } => name,
+        }
+    }
+
+    /// Gives the reward to player A of the scenario
+    fn apply(&self, scenario: &mut Scenario) {
+        match self {
+            Self::PlantShape {
+                shape, cooldown, ..
+            } => scenario.player_a.plants.push(PlantSetup {
+                shape: shape.clone(),
+                cooldown: *cooldown,
+                time_left: *cooldown,
+            }),
+            Self::StartingShape { shape, .. } => scenario.player_a.buffer.push(shape.clone()),
+            Self::Ability {
+                ability: Ability::FastGrowth,
+                ..
+            } => {
+                for plant in &mut scenario.player_a.plants {
+                    plant.cooldown = plant.cooldown.saturating_sub(1).max(1);
+                    plant.time_left = plant.time_left.min(plant.cooldown);
+                }
+            }
+        }
+    }
+
+    /// Abilities change the whole setup, so they are given after the shapes
+    fn is_ability(&self) -> bool {
+        matches!(self, Self::Ability { .. })
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        match self {
+            Self::PlantShape { name, shape, .. } | Self::StartingShape { name, shape } => {
+                if shape.0.is_empty() || !shape.is_connected() {
+                    return Err(format!("{name}: the shape must be connected and not empty"));
+                }
+                Ok(())
+            }
+            Self::Ability { .. } => Ok(()),
+        }
+    }
+}
+
+impl Campaign {
+    /// Checks every level, the campaign cannot be played with a broken one
+    /// since the progress is the number of completed levels
+    fn validate(&self) -> Result<(), String> {
+        for (index, level) in self.levels.iter().enumerate() {
+            let check = || -> Result<(), String> {
+                level.scenario.validate()?;
+                if !agents::AGENT_NAMES.contains(&level.agent.as_str()) {
+                    return Err(format!("unknown agent {}", level.agent));
+                }
+                if let Some(unlock) = &level.unlock {
+                    unlock.validate()?;
+                }
+                Ok(())
+            };
+            check().map_err(|error| format!("level {}: {error}", index + 1))?;
+        }
+        Ok(())
+    }
+}
+
+/// The campaign map: shows the levels and starts battles
+pub struct CampaignMap {
+    geng: Geng,
+    assets: Rc<Assets>,
+    camera: Camera2d,
+    campaign: Campaign,
+    /// Why the campaign could not be loaded
+    error: Option<String>,
+    profile: Profile,
+    selected: usize,
+    /// The level being played and the slot for its result
+    playing: Option<(usize, Rc<Cell<Option<GoalStatus>>>)>,
+    transition: Option<geng::Transition>,
+}
+
+impl CampaignMap {
+    pub fn new(geng: &Geng, assets: &Rc<Assets>) -> Self {
+        let loaded = serde_json::from_str::<Campaign>(&assets.campaign)
+            .map_err(|error| error.to_string())
+            .and_then(|campaign| campaign.validate().map(|()| campaign));
+        let (campaign, error) = match loaded {
+            Ok(campaign) => (campaign, None),
+            Err(error) => {
+                error!("Failed to load the campaign: {error}");
+                (Campaign { levels: vec![] }, Some(error))
+            }
+        };
+        let profile = Profile::load();
+        Self {
+            geng: geng.clone(),
+            assets: assets.clone(),
+            camera: Camera2d {
+                center: vec2(0.0, 0.0),
+                rotation: 0.0,
+                fov: 20.0,
+            },
+            selected: profile
+                .completed
+                .min(campaign.levels.len().saturating_sub(1)),
+            campaign,
+            error,
+            profile,
+            playing: None,
+            transition: None,
+        }
+    }
+
+    fn is_unlocked(&self, level: usize) -> bool {
+        level <= self.profile.completed
+    }
+
+    fn start(&mut self) {
+        if !self.is_unlocked(self.selected) {
+            return;
+        }
+        let level = match self.campaign.levels.get(self.selected) {
+            Some(level) => level,
+            None => return,
+        };
+        let agent = match agents::agent_by_name(&level.agent, global_rng().gen()) {
+            Some(agent) => agent,
+            None => {
+                error!("Unknown agent in the campaign: {}", level.agent);
+                return;
+            }
+        };
+        let mut scenario = level.scenario.clone();
+        let unlocks = &self.profile.unlocks;
+        for unlock in unlocks
+            .iter()
+            .filter(|unlock| !unlock.is_ability())
+            .chain(unlocks.iter().filter(|unlock| unlock.is_ability()))
+        {
+            unlock.apply(&mut scenario);
+        }
+        let result = Rc::new(Cell::new(None));
+        let game = game::Game::new_campaign(&self.geng, &self.assets, scenario, agent);
+        let game = Match::new(&self.geng, game).report_to(result.clone());
+        self.playing = Some((self.selected, result));
+        self.transition = Some(geng::Transition::Push(Box::new(game)));
+    }
+
+    /// Records the result of the last battle once the player returns to the map
+    fn check_result(&mut self) {
+        let (level, result) = match &self.playing {
+            Some(playing) => playing,
+            None => return,
+        };
+        let status = match result.take() {
+            Some(status) => status,
+            None => return,
+        };
+        let level = *level;
+        self.playing = None;
+        if status != GoalStatus::Completed || level != self.profile.completed {
+            return;
+        }
+        self.profile.completed += 1;
+        if let Some(unlock) = &self.campaign.levels[level].unlock {
+            if !self
+                .profile
+                .unlocks
+                .iter()
+                .any(|known| known.name() == unlock.name())
+            {
+                self.profile.unlocks.push(unlock.clone());
+            }
+        }
+        self.profile.save();
+        self.selected = self
+            .profile
+            .completed
+            .min(self.campaign.levels.len().saturating_sub(1));
+    }
+}
+
+impl geng::State for CampaignMap {
+    fn update(&mut self, _delta_time: f64) {
+        self.check_result();
+    }
+
+    fn draw(&mut self, framebuffer: &mut ugli::Framebuffer) {
+        ugli::clear(framebuffer, Some(Color::BLACK), None);
+        let font = self.geng.default_font();
+        let size = 1.0;
+        font.draw(
+            framebuffer,
+            &self.camera,
+            "Campaign",
+            vec2(0.0, 7.0),
+            geng::TextAlign::CENTER,
+            size * 1.5,
+            Color::WHITE,
+        );
+        for (index, level) in self.campaign.levels.iter().enumerate() {
+            let status = if index < self.profile.completed {
+                "completed"
+            } else if self.is_unlocked(index) {
+                "available"
+            } else {
+                "locked"
+            };
+            let color = if index == self.selected {
+                Color::BLUE
+            } else if self.is_unlocked(index) {
+                Color::WHITE
+            } else {
+                Color::GRAY
+            };
+            font.draw(
+                framebuffer,
+                &self.camera,
+                &format!(
+                    "{}. {} ({}) vs {}",
+                    index + 1,
+                    level.scenario.name,
+                    status,
+                    level.agent
+                ),
+                vec2(0.0, 4.0 - index as f32 * size * 1.5),
+                geng::TextAlign::CENTER,
+                size,
+                color,
+            );
+        }
+        let unlocks = self
+            .profile
+            .unlocks
+            .iter()
+            .map(|unlock| unlock.name())
+            .collect::<Vec<_>>()
+            .join(", ");
+        if let Some(error) = &self.error {
+            font.draw(
+                framebuffer,
+                &self.camera,
+                &format!("Failed to load the campaign: {error}"),
+                vec2(0.0, 0.0),
+                geng::TextAlign::CENTER,
+                size * 0.5,
+                Color::RED,
+            );
+        }
+        if !unlocks.is_empty() {
+            font.draw(
+                framebuffer,
+                &self.camera,
+                &format!("Unlocked: {unlocks}"),
+                vec2(0.0, -6.0),
+                geng::TextAlign::CENTER,
+                size * 0.7,
+                Color::GREEN,
+            );
+        }
+        font.draw(
+            framebuffer,
+            &self.camera,
+            "Up/Down to choose, Enter to fight, Escape to go back",
+            vec2(0.0, -8.0),
+            geng::TextAlign::CENTER,
+            size * 0.6,
+            Color::GRAY,
+        );
+    }
+
+    fn handle_event(&mut self, event: geng::Event) {
+        if let geng::Event::KeyDown { key } = event {
+            match key {
+                geng::Key::Up => self.selected = self.selected.saturating_sub(1),
+                geng::Key::Down => {
+                    self.selected =
+                        (self.selected + 1).min(self.campaign.levels.len().saturating_sub(1))
+                }
+                geng::Key::Enter => self.start(),
+                geng::Key::Escape => self.transition = Some(geng::Transition::Pop),
+                _ => {}
+            }
+        }
+    }
+
+    fn transition(&mut self) -> Option<geng::Transition> {
+        self.transition.take()
+    }
+}
@@ -0,0 +1,141 @@
+use super::*;
+
+use game::{Game, Opponent};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MenuOption {
+    PlayAi,
+    Hotseat,
+    Campaign,
+    Puzzles,
+    LoadGame,
+    Settings,
+    #[cfg(not(target_arch = "wasm32"))]
+    Quit,
+}
+
+const MENU_OPTIONS: &[MenuOption] = &[
+    MenuOption::PlayAi,
+    MenuOption::Hotseat,
+    MenuOption::Campaign,
+    MenuOption::Puzzles,
+    MenuOption::LoadGame,
+    MenuOption::Settings,
+    #[cfg(not(target_arch = "wasm32"))]
+    MenuOption::Quit,
+];
+
+/// The first screen of the game, every other screen returns to it
+pub struct MainMenu {
+    geng: Geng,
+    assets: Rc<Assets>,
+    camera: Camera2d,
+    selected: usize,
+    transition: Option<geng::Transition>,
+}
+
+impl MainMenu {
+    pub fn new(geng: &Geng, assets: &Rc<Assets>) -> Self {
+        Self {
+            geng: geng.clone(),
+            assets: assets.clone(),
+            camera: Camera2d {
+                center: vec2(0.0, 0.0),
+                rotation: 0.0,
+                fov: 20.0,
+            },
+            selected: 0,
+            transition: None,
+        }
+    }
+
+    fn is_enabled(&self, option: MenuOption) -> bool {
+        match option {
+            MenuOption::LoadGame => has_saved_game(),
+            _ => true,
+        }
+    }
+
+    fn new_game(&self, opponent: Opponent) -> Box<dyn geng::State> {
+        let settings = Settings::load();
+        let game = Game::new(&self.geng, &self.assets, settings.turn_mode, opponent);
+        Box::new(Match::new(&self.geng, game))
+    }
+
+    fn select(&mut self, option: MenuOption) {
+        let state: Box<dyn geng::State> = match option {
+            MenuOption::PlayAi => self.new_game(Opponent::Ai),
+            MenuOption::Hotseat => self.new_game(Opponent::Human),
+            MenuOption::Campaign => Box::new(CampaignMap::new(&self.geng, &self.assets)),
+            MenuOption::Puzzles => Box::new(PuzzleSelect::new(&self.geng, &self.assets)),
+            MenuOption::LoadGame => {
+                let model = match load_game() {
+                    Some(model) => model,
+                    None => return,
+                };
+                let game = Game::new_saved(&self.geng, &self.assets, model);
+                Box::new(Match::new(&self.geng, game))
+            }
+            MenuOption::Settings => Box::new(SettingsMenu::new(&self.geng)),
+            #[cfg(not(target_arch = "wasm32"))]
+            MenuOption::Quit => std::process::exit(0),
+        };
+        self.transition = Some(geng::Transition::Push(state));
+    }
+}
+
+impl geng::State for MainMenu {
+    fn draw(&mut self, framebuffer: &mut ugli::Framebuffer) {
+        ugli::clear(framebuffer, Some(Color::BLACK), None);
+        let options: Vec<(String, bool)> = MENU_OPTIONS
+            .iter()
+            .map(|&option| {
+                let name = match option {
+                    MenuOption::PlayAi => "Play against AI",
+                    MenuOption::Hotseat => "Hotseat",
+                    MenuOption::Campaign => "Campaign",
+                    MenuOption::Puzzles => "Puzzles",
+                    MenuOption::LoadGame => "Load game",
+                    MenuOption::Settings => "Settings",
+                    #[cfg(not(target_arch = "wasm32"))]
+                    MenuOption::Quit => "Quit",
+                };
+                (name.to_owned(), self.is_enabled(option))
+            })
+            .collect();
+        draw_list(
+            &self.geng,
+            &self.camera,
+            framebuffer,
+            "Untitled Circle Game",
+            &options,
+            self.selected,
+        );
+        draw_hint(
+            &self.geng,
+            &self.camera,
+            framebuffer,
+            "Up/Down to choose, Enter to select",
+        );
+    }
+
+    fn handle_event(&mut self, event: geng::Event) {
+        if let geng::Event::KeyDown { key } = event {
+            match key {
+                geng::Key::Up => self.selected = self.selected.saturating_sub(1),
+                geng::Key::Down => self.selected = (self.selected + 1).min(MENU_OPTIONS.len() - 1),
+                geng::Key::Enter => {
+                    let option = MENU_OPTIONS[self.selected];
+                    if self.is_enabled(option) {
+                        self.select(option);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn transition(&mut self) -> Option<geng::Transition> {
+        self.transition.take()
+    }
+}
@@ -0,0 +1,83 @@
+use super::*;
+
+mod bindings;
+mod campaign;
+mod main_menu;
+mod playing;
+mod puzzle_select;
+mod results;
+mod settings;
+
+pub use bindings::*;
+pub use campaign::*;
+pub use main_menu::*;
+pub use playing::*;
+pub use puzzle_select::*;
+pub use results::*;
+pub use settings::*;
+
+/// Draws a title with a vertical list of options, highlighting the selected one.
+/// Disabled options are grayed out.
+fn draw_list(
+    geng: &Geng,
+    camera: &Camera2d,
+    framebuffer: &mut ugli::Framebuffer,
+    title: &str,
+    options: &[(String, bool)],
+    selected: usize,
+) {
+    let font = geng.default_font();
+    let size = 1.0;
+    font.draw(
+        framebuffer,
+        camera,
+        title,
+        vec2(0.0, 6.0),
+        geng::TextAlign::CENTER,
+        size * 1.5,
+        Color::WHITE,
+    );
+    for (index, (option, enabled)) in options.iter().enumerate() {
+        let color = if index == selected {
+            Color::BLUE
+        } else if *enabled {
+            Color::WHITE
+        } else {
+            Color::GRAY
+        };
+        font.draw(
+            framebuffer,
+            camera,
+            option,
+            vec2(0.0, 3.0 - index as f32 * size * 1.5),
+            geng::TextAlign::CENTER,
+            size,
+            color,
+        );
+    }
+}
+
+/// Draws a hint at the bottom of the screen
+fn draw_hint(geng: &Geng, camera: &Camera2d, framebuffer: &mut ugli::Framebuffer, text: &str) {
+    geng.default_font().draw(
+        framebuffer,
+        camera,
+        text,
+        vec2(0.0, -8.0),
+        geng::TextAlign::CENTER,
+        0.6,
+        Color::GRAY,
+    );
+}
+
+/// Darkens everything drawn before so that a menu can be drawn on top
+fn draw_shade(geng: &Geng, camera: &Camera2d, framebuffer: &mut ugli::Framebuffer) {
+    let framebuffer_size = framebuffer.size().map(|x| x as f32);
+    let bounds = AABB::point(camera.center).extend_symmetric(
+        vec2(
+            camera.fov / framebuffer_size.y * framebuffer_size.x,
+            camera.fov,
+        ) / 2.0,
+    );
+    draw_2d::Quad::new(bounds, Color::rgba(0.0, 0.0, 0.0, 0.7)).draw_2d(geng, framebuffer, camera);
+}
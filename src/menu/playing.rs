@@ -0,0 +1,173 @@
+use super::*;
+
+use game::input::{Bindings, InputAction};
+use game::model::{GoalStatus, Model};
+use game::{Game, GameResult};
+use std::cell::Cell;
+
+/// Where the game is saved from the pause menu
+const SAVE_PATH: &str = "savegame.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PauseOption {
+    Resume,
+    Save,
+    Quit,
+}
+
+const PAUSE_OPTIONS: [PauseOption; 3] = [PauseOption::Resume, PauseOption::Save, PauseOption::Quit];
+
+/// Runs a match: pauses it on Escape and shows the results once it is over
+pub struct Match {
+    geng: Geng,
+    camera: Camera2d,
+    game: Game,
+    bindings: Bindings,
+    /// Selected option of the pause menu, `None` while playing
+    paused: Option<usize>,
+    /// Where the outcome of the scenario is reported once it is over
+    report: Option<Rc<Cell<Option<GoalStatus>>>>,
+    transition: Option<geng::Transition>,
+}
+
+impl Match {
+    pub fn new(geng: &Geng, game: Game) -> Self {
+        Self {
+            geng: geng.clone(),
+            camera: Camera2d {
+                center: vec2(0.0, 0.0),
+                rotation: 0.0,
+                fov: 20.0,
+            },
+            game,
+            bindings: Bindings::load(),
+            paused: None,
+            report: None,
+            transition: None,
+        }
+    }
+
+    /// Writes the outcome of the scenario to `report` once the match is over
+    pub fn report_to(self, report: Rc<Cell<Option<GoalStatus>>>) -> Self {
+        Self {
+            report: Some(report),
+            ..self
+        }
+    }
+
+    fn is_enabled(&self, option: PauseOption) -> bool {
+        match option {
+            PauseOption::Save => self.game.save().is_some(),
+            _ => true,
+        }
+    }
+
+    fn select(&mut self, option: PauseOption) {
+        match option {
+            PauseOption::Resume => self.paused = None,
+            PauseOption::Save => {
+                if let Some(model) = self.game.save() {
+                    save_game(model);
+                    self.paused = None;
+                }
+            }
+            PauseOption::Quit => self.transition = Some(geng::Transition::Pop),
+        }
+    }
+
+    fn handle_pause_event(&mut self, selected: usize, event: geng::Event) {
+        if let geng::Event::KeyDown { key } = event {
+            match key {
+                geng::Key::Up => self.paused = Some(selected.saturating_sub(1)),
+                geng::Key::Down => self.paused = Some((selected + 1).min(PAUSE_OPTIONS.len() - 1)),
+                geng::Key::Enter => {
+                    let option = PAUSE_OPTIONS[selected];
+                    if self.is_enabled(option) {
+                        self.select(option);
+                    }
+                }
+                geng::Key::Escape => self.paused = None,
+                _ => {}
+            }
+        }
+    }
+}
+
+impl geng::State for Match {
+    fn draw(&mut self, framebuffer: &mut ugli::Framebuffer) {
+        geng::State::draw(&mut self.game, framebuffer);
+        if let Some(selected) = self.paused {
+            draw_shade(&self.geng, &self.camera, framebuffer);
+            let options: Vec<(String, bool)> = PAUSE_OPTIONS
+                .iter()
+                .map(|&option| {
+                    let name = match option {
+                        PauseOption::Resume => "Resume",
+                        PauseOption::Save => "Save game",
+                        PauseOption::Quit => "Quit to menu",
+                    };
+                    (name.to_owned(), self.is_enabled(option))
+                })
+                .collect();
+            draw_list(
+                &self.geng,
+                &self.camera,
+                framebuffer,
+                "Paused",
+                &options,
+                selected,
+            );
+        }
+    }
+
+    fn handle_event(&mut self, event: geng::Event) {
+        if let Some(selected) = self.paused {
+            self.handle_pause_event(selected, event);
+            return;
+        }
+        // Cancelling the current interaction takes priority over pausing
+        if self.bindings.pressed(InputAction::Pause, &event) && self.game.is_idle() {
+            self.paused = Some(0);
+            return;
+        }
+        geng::State::handle_event(&mut self.game, event);
+    }
+
+    fn update(&mut self, delta_time: f64) {
+        // Online games keep going while paused
+        geng::State::update(&mut self.game, delta_time);
+
+        if self.transition.is_some() {
+            return;
+        }
+        if let Some(result) = self.game.result() {
+            if let (Some(report), GameResult::Scenario(status)) = (&self.report, result) {
+                report.set(Some(status));
+            }
+            let results = Results::new(&self.geng, result, self.game.turn());
+            self.transition = Some(geng::Transition::Switch(Box::new(results)));
+        }
+    }
+
+    fn transition(&mut self) -> Option<geng::Transition> {
+        self.transition.take()
+    }
+}
+
+pub fn save_game(model: &Model) {
+    let save = serde_json::to_string(model).expect("Failed to serialize the game");
+    if let Err(error) = std::fs::write(SAVE_PATH, save) {
+        error!("Failed to save the game: {error}");
+    }
+}
+
+pub fn load_game() -> Option<Model> {
+    let save = std::fs::read_to_string(SAVE_PATH).ok()?;
+    serde_json::from_str(&save)
+        .map_err(|error| error!("Failed to parse the saved game: {error}"))
+        .ok()
+}
+
+pub fn has_saved_game() -> bool {
+    std::path::Path::new(SAVE_PATH).exists()
+}
@@ -0,0 +1,141 @@
+use super::*;
+
+use game::model::Scenario;
+
+/// Puzzles made by the player, listed after the bundled ones
+const USER_PUZZLES_PATH: &str = "custom_puzzles.json";
+
+/// Lists the puzzles from the assets and starts the selected one
+pub struct PuzzleSelect {
+    geng: Geng,
+    assets: Rc<Assets>,
+    camera: Camera2d,
+    puzzles: Vec<Scenario>,
+    /// Puzzles that could not be loaded, with the reason
+    errors: Vec<String>,
+    selected: usize,
+    transition: Option<geng::Transition>,
+}
+
+impl PuzzleSelect {
+    pub fn new(geng: &Geng, assets: &Rc<Assets>) -> Self {
+        let mut puzzles = Vec::new();
+        let mut errors = Vec::new();
+        load_puzzles(&assets.puzzles, &mut puzzles, &mut errors);
+        if let Ok(file) = std::fs::read_to_string(USER_PUZZLES_PATH) {
+            load_puzzles(&file, &mut puzzles, &mut errors);
+        }
+        for error in &errors {
+            error!("Failed to load a puzzle: {error}");
+        }
+        Self {
+            geng: geng.clone(),
+            assets: assets.clone(),
+            camera: Camera2d {
+                center: vec2(0.0, 0.0),
+                rotation: 0.0,
+                fov: 20.0,
+            },
+            puzzles,
+            errors,
+            selected: 0,
+            transition: None,
+        }
+    }
+
+    fn start(&mut self) {
+        if let Some(scenario) = self.puzzles.get(self.selected) {
+            let game = game::Game::new_scenario(&self.geng, &self.assets, scenario.clone());
+            let game = Match::new(&self.geng, game);
+            self.transition = Some(geng::Transition::Push(Box::new(game)));
+        }
+    }
+}
+
+/// Adds the valid puzzles of a JSON list, the invalid ones are reported in `errors`
+fn load_puzzles(file: &str, puzzles: &mut Vec<Scenario>, errors: &mut Vec<String>) {
+    let loaded: Vec<Scenario> = match serde_json::from_str(file) {
+        Ok(loaded) => loaded,
+        Err(error) => {
+            errors.push(error.to_string());
+            return;
+        }
+    };
+    for puzzle in loaded {
+        match puzzle.validate() {
+            Ok(()) => puzzles.push(puzzle),
+            Err(error) => errors.push(error),
+        }
+    }
+}
+
+impl geng::State for PuzzleSelect {
+    fn draw(&mut self, framebuffer: &mut ugli::Framebuffer) {
+        ugli::clear(framebuffer, Some(Color::BLACK), None);
+        let font = self.geng.default_font();
+        let size = 1.0;
+        font.draw(
+            framebuffer,
+            &self.camera,
+            "Puzzles",
+            vec2(0.0, 6.0),
+            geng::TextAlign::CENTER,
+            size * 1.5,
+            Color::WHITE,
+        );
+        for (index, puzzle) in self.puzzles.iter().enumerate() {
+            let color = if index == self.selected {
+                Color::BLUE
+            } else {
+                Color::GRAY
+            };
+            font.draw(
+                framebuffer,
+                &self.camera,
+                &format!("{}. {}", index + 1, puzzle.name),
+                vec2(0.0, 3.0 - index as f32 * size * 1.5),
+                geng::TextAlign::CENTER,
+                size,
+                color,
+            );
+        }
+        if !self.errors.is_empty() {
+            font.draw(
+                framebuffer,
+                &self.camera,
+                &format!("Failed to load: {}", self.errors.join("; ")),
+                vec2(0.0, -7.0),
+                geng::TextAlign::CENTER,
+                size * 0.5,
+                Color::RED,
+            );
+        }
+        font.draw(
+            framebuffer,
+            &self.camera,
+            "Up/Down to choose, Enter to play, Escape to go back",
+            vec2(0.0, -8.0),
+            geng::TextAlign::CENTER,
+            size * 0.6,
+            Color::GRAY,
+        );
+    }
+
+    fn handle_event(&mut self, event: geng::Event) {
+        if let geng::Event::KeyDown { key } = event {
+            match key {
+                geng::Key::Up => self.selected = self.selected.saturating_sub(1),
+                geng::Key::Down => {
+                    self.selected = (self.selected + 1).min(self.puzzles.len().saturating_sub(1))
+                }
+                geng::Key::Enter => self.start(),
+                geng::Key::Escape => self.transition = Some(geng::Transition::Pop),
+                _ => {}
+            }
+        }
+    }
+
+    fn transition(&mut self) -> Option<geng::Transition> {
+        self.transition.take()
+    }
+}
@@ -0,0 +1,81 @@
+use super::*;
+
+use game::model::{GoalStatus, Turns};
+use game::GameResult;
+
+/// Shown after a match has ended, returns to the previous menu on Enter
+pub struct Results {
+    geng: Geng,
+    camera: Camera2d,
+    result: GameResult,
+    turns: Turns,
+    transition: Option<geng::Transition>,
+}
+
+impl Results {
+    pub fn new(geng: &Geng, result: GameResult, turns: Turns) -> Self {
+        Self {
+            geng: geng.clone(),
+            camera: Camera2d {
+                center: vec2(0.0, 0.0),
+                rotation: 0.0,
+                fov: 20.0,
+            },
+            result,
+            turns,
+            transition: None,
+        }
+    }
+}
+
+impl geng::State for Results {
+    fn draw(&mut self, framebuffer: &mut ugli::Framebuffer) {
+        ugli::clear(framebuffer, Some(Color::BLACK), None);
+        let (title, color) = match self.result {
+            GameResult::Scenario(GoalStatus::Completed) | GameResult::Local { won: true } => {
+                ("Victory!", Color::GREEN)
+            }
+            GameResult::Scenario(_) | GameResult::Local { won: false } => ("Defeat", Color::RED),
+            GameResult::Winner { player_a: true } => ("Blue player wins!", Color::BLUE),
+            GameResult::Winner { player_a: false } => ("Red player wins!", Color::RED),
+        };
+        let font = self.geng.default_font();
+        font.draw(
+            framebuffer,
+            &self.camera,
+            title,
+            vec2(0.0, 2.0),
+            geng::TextAlign::CENTER,
+            2.0,
+            color,
+        );
+        font.draw(
+            framebuffer,
+            &self.camera,
+            &format!("Turns played: {}", self.turns),
+            vec2(0.0, -1.0),
+            geng::TextAlign::CENTER,
+            1.0,
+            Color::WHITE,
+        );
+        draw_hint(
+            &self.geng,
+            &self.camera,
+            framebuffer,
+            "Press Enter to continue",
+        );
+    }
+
+    fn handle_event(&mut self, event: geng::Event) {
+        if let geng::Event::KeyDown {
+            key: geng::Key::Enter | geng::Key::Escape,
+        } = event
+        {
+            self.transition = Some(geng::Transition::Pop);
+        }
+    }
+
+    fn transition(&mut self) -> Option<geng::Transition> {
+        self.transition.take()
+    }
+}
@@ -0,0 +1,134 @@
+use super::*;
+
+use game::TurnMode;
+
+/// Where the settings are saved between runs
+const SETTINGS_PATH: &str = "settings.json";
+
+/// Preferences of the local player
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Turn mode of new local games
+    pub turn_mode: TurnMode,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            turn_mode: TurnMode::Alternating,
+        }
+    }
+}
+
+impl Settings {
+    pub fn load() -> Self {
+        match std::fs::read_to_string(SETTINGS_PATH) {
+            Ok(settings) => serde_json::from_str(&settings).unwrap_or_else(|error| {
+                error!("Failed to parse the settings, using the defaults: {error}");
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn save(&self) {
+        let settings =
+            serde_json::to_string_pretty(self).expect("Failed to serialize the settings");
+        if let Err(error) = std::fs::write(SETTINGS_PATH, settings) {
+            error!("Failed to save the settings: {error}");
+        }
+    }
+}
+
+/// Edits the settings, saving them on every change
+pub struct SettingsMenu {
+    geng: Geng,
+    camera: Camera2d,
+    settings: Settings,
+    selected: usize,
+    transition: Option<geng::Transition>,
+}
+
+impl SettingsMenu {
+    pub fn new(geng: &Geng) -> Self {
+        Self {
+            geng: geng.clone(),
+            camera: Camera2d {
+                center: vec2(0.0, 0.0),
+                rotation: 0.0,
+                fov: 20.0,
+            },
+            settings: Settings::load(),
+            selected: 0,
+            transition: None,
+        }
+    }
+
+    fn options(&self) -> Vec<(String, bool)> {
+        let turn_mode = match self.settings.turn_mode {
+            TurnMode::Alternating => "alternating",
+            TurnMode::Simultaneous => "simultaneous",
+        };
+        vec![
+            (format!("Turns: {turn_mode}"), true),
+            ("Controls".to_owned(), true),
+            ("Back".to_owned(), true),
+        ]
+    }
+
+    fn activate(&mut self) {
+        match self.selected {
+            0 => {
+                self.settings.turn_mode = match self.settings.turn_mode {
+                    TurnMode::Alternating => TurnMode::Simultaneous,
+                    TurnMode::Simultaneous => TurnMode::Alternating,
+                };
+                self.settings.save();
+            }
+            1 => {
+                let bindings = BindingsMenu::new(&self.geng);
+                self.transition = Some(geng::Transition::Push(Box::new(bindings)));
+            }
+            _ => self.transition = Some(geng::Transition::Pop),
+        }
+    }
+}
+
+impl geng::State for SettingsMenu {
+    fn draw(&mut self, framebuffer: &mut ugli::Framebuffer) {
+        ugli::clear(framebuffer, Some(Color::BLACK), None);
+        draw_list(
+            &self.geng,
+            &self.camera,
+            framebuffer,
+            "Settings",
+            &self.options(),
+            self.selected,
+        );
+        draw_hint(
+            &self.geng,
+            &self.camera,
+            framebuffer,
+            "Up/Down to choose, Enter to change, Escape to go back",
+        );
+    }
+
+    fn handle_event(&mut self, event: geng::Event) {
+        if let geng::Event::KeyDown { key } = event {
+            match key {
+                geng::Key::Up => self.selected = self.selected.saturating_sub(1),
+                geng::Key::Down => {
+                    self.selected = (self.selected + 1).min(self.options().len() - 1)
+                }
+                geng::Key::Enter => self.activate(),
+                geng::Key::Escape => self.transition = Some(geng::Transition::Pop),
+                _ => {}
+            }
+        }
+    }
+
+    fn transition(&mut self) -> Option<geng::Transition> {
+        self.transition.take()
+    }
+}
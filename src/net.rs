@@ -0,0 +1,23 @@
+use super::*;
+
+use game::model::{PlayerAction, PlayerView, ViewUpdate};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ClientMessage {
+    Action(PlayerAction),
+    /// Give up the seat and watch the game instead
+    Spectate,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ServerMessage {
+    /// Sent on connection and when the role changes:
+    /// the seat of the client, `None` for spectators
+    Setup { seat: Option<bool> },
+    /// The state of the game as the client is allowed to see it
+    View(PlayerView),
+    /// Changes to the last view sent to the client
+    Update(ViewUpdate),
+}
+
+pub type Connection = geng::net::client::Connection<ServerMessage, ClientMessage>;
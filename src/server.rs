@@ -0,0 +1,187 @@
+use super::*;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use game::model::{Model, Opponent, PlayerView, TurnMode, ViewUpdate};
+use net::{ClientMessage, ServerMessage};
+
+type ClientId = usize;
+
+/// How often delayed views are sent to the spectators
+const SPECTATOR_FLUSH_INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
+
+struct ServerState {
+    model: Model,
+    next_client_id: ClientId,
+    /// Clients playing the game with their seats and the last view they were sent
+    players: HashMap<ClientId, (bool, Box<dyn geng::net::Sender<ServerMessage>>, PlayerView)>,
+    /// Read-only clients watching the game
+    spectators: HashMap<ClientId, Box<dyn geng::net::Sender<ServerMessage>>>,
+    /// How far behind the players the spectators see the game
+    spectator_delay: std::time::Duration,
+    /// The game as currently seen by the spectators
+    spectator_view: PlayerView,
+    /// Views not yet sent to the spectators
+    spectator_queue: VecDeque<(std::time::Instant, PlayerView)>,
+}
+
+/// Owns the authoritative model, validates the actions of the clients
+/// and sends each of them the part of the state they are allowed to see
+pub struct Server {
+    state: Arc<Mutex<ServerState>>,
+    inner: geng::net::Server<ServerApp>,
+}
+
+struct ServerApp {
+    state: Arc<Mutex<ServerState>>,
+}
+
+struct Client {
+    id: ClientId,
+    state: Arc<Mutex<ServerState>>,
+}
+
+impl Server {
+    pub fn new(addr: &str, spectator_delay: std::time::Duration) -> Self {
+        let model = Model::with_mode(TurnMode::Alternating, Opponent::Human);
+        let state = Arc::new(Mutex::new(ServerState {
+            spectator_view: model.view(None),
+            model,
+            next_client_id: 0,
+            players: HashMap::new(),
+            spectators: HashMap::new(),
+            spectator_delay,
+            spectator_queue: VecDeque::new(),
+        }));
+        let app = ServerApp {
+            state: state.clone(),
+        };
+        Self {
+            state,
+            inner: geng::net::Server::new(app, addr),
+        }
+    }
+
+    pub fn handle(&self) -> geng::net::ServerHandle {
+        self.inner.handle()
+    }
+
+    pub fn run(self) {
+        let state = self.state.clone();
+        std::thread::spawn(move || loop {
+            std::thread::sleep(SPECTATOR_FLUSH_INTERVAL);
+            state.lock().unwrap().flush_spectators();
+        });
+        self.inner.run();
+    }
+}
+
+impl geng::net::server::App for ServerApp {
+    type Client = Client;
+    type ServerMessage = ServerMessage;
+    type ClientMessage = ClientMessage;
+
+    fn connect(&mut self, mut sender: Box<dyn geng::net::Sender<ServerMessage>>) -> Client {
+        let mut state = self.state.lock().unwrap();
+        let id = state.next_client_id;
+        state.next_client_id += 1;
+
+        // The first free seat is taken by the new client
+        let taken: Vec<bool> = state.players.values().map(|(seat, ..)| *seat).collect();
+        match [true, false].into_iter().find(|seat| !taken.contains(seat)) {
+            Some(player_a) => {
+                info!(
+                    "Client {id} joined as player {}",
+                    if player_a { "A" } else { "B" }
+                );
+                sender.send(ServerMessage::Setup {
+                    seat: Some(player_a),
+                });
+                let view = state.model.view(Some(player_a));
+                sender.send(ServerMessage::View(view.clone()));
+                state.players.insert(id, (player_a, sender, view));
+            }
+            None => {
+                info!("Client {id} joined as a spectator");
+                state.add_spectator(id, sender);
+            }
+        }
+
+        Client {
+            id,
+            state: self.state.clone(),
+        }
+    }
+}
+
+impl geng::net::Receiver<ClientMessage> for Client {
+    fn handle(&mut self, message: ClientMessage) {
+        let mut state = self.state.lock().unwrap();
+        let player_a = match state.players.get(&self.id) {
+            Some((seat, ..)) => *seat,
+            None => return,
+        };
+        match message {
+            ClientMessage::Spectate => {
+                info!("Client {} gave up their seat to spectate", self.id);
+                let (_, sender, _) = state.players.remove(&self.id).unwrap();
+                state.add_spectator(self.id, sender);
+            }
+            ClientMessage::Action(action) => {
+                if !state.model.handle_remote_action(player_a, action.clone()) {
+                    warn!("Rejected action of client {}: {action:?}", self.id);
+                    return;
+                }
+                state.broadcast();
+            }
+        }
+    }
+}
+
+impl Drop for Client {
+    fn drop(&mut self) {
+        let mut state = self.state.lock().unwrap();
+        if state.players.remove(&self.id).is_some() || state.spectators.remove(&self.id).is_some() {
+            info!("Client {} left", self.id);
+        }
+    }
+}
+
+impl ServerState {
+    fn add_spectator(
+        &mut self,
+        id: ClientId,
+        mut sender: Box<dyn geng::net::Sender<ServerMessage>>,
+    ) {
+        sender.send(ServerMessage::Setup { seat: None });
+        sender.send(ServerMessage::View(self.spectator_view.clone()));
+        self.spectators.insert(id, sender);
+    }
+
+    /// Sends the changes to the players and queues them for the spectators
+    fn broadcast(&mut self) {
+        for (seat, sender, sent) in self.players.values_mut() {
+            let view = self.model.view(Some(*seat));
+            sender.send(ServerMessage::Update(ViewUpdate::new(sent, &view)));
+            *sent = view;
+        }
+        self.spectator_queue
+            .push_back((std::time::Instant::now(), self.model.view(None)));
+        self.flush_spectators();
+    }
+
+    /// Sends the views that are older than the delay to the spectators
+    fn flush_spectators(&mut self) {
+        while let Some((time, _)) = self.spectator_queue.front() {
+            if time.elapsed() < self.spectator_delay {
+                break;
+            }
+            let (_, view) = self.spectator_queue.pop_front().unwrap();
+            let update = ViewUpdate::new(&self.spectator_view, &view);
+            for sender in self.spectators.values_mut() {
+                sender.send(ServerMessage::Update(update.clone()));
+            }
+            self.spectator_view = view;
+        }
+    }
+}
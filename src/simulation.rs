@@ -0,0 +1,84 @@
+//! Matches between AI agents without any rendering
+
+use super::*;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::hash::{Hash, Hasher};
+
+use game::model::logic::agents::Agent;
+use game::model::{Model, Opponent, PlayerAction, Rules, TurnMode, Turns};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    WinA,
+    WinB,
+    Draw,
+}
+
+/// Number of actions of each kind made by a player
+#[derive(Debug, Default, Serialize)]
+pub struct ActionCounts(pub BTreeMap<String, u64>);
+
+/// Derives the seed of an agent from the seed of the match,
+/// so that the agents of both seats do not share their random choices
+pub fn agent_seed(match_seed: u64, player_a: bool) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    (match_seed, player_a).hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Plays a match until one of the players wins or the turn limit is reached
+pub fn play_match(
+    rules: &Rules,
+    max_turns: Turns,
+    mut agents: [&mut dyn Agent; 2],
+    mut counts: [&mut ActionCounts; 2],
+) -> (Outcome, Turns) {
+    // Both sides are controlled from the outside, so the model should not run its own AI
+    let mut model = Model::with_rules(rules, Opponent::Human);
+    // Planned actions are only checked when the simultaneous turn is resolved
+    let simultaneous = model.turn_mode == TurnMode::Simultaneous;
+    while model.turn < max_turns {
+        if let Some(winner) = model.winner() {
+            let outcome = if winner { Outcome::WinA } else { Outcome::WinB };
+            return (outcome, model.turn);
+        }
+        let player_a = model.player_a_turn;
+        let index = if player_a { 0 } else { 1 };
+        let view = model.view(Some(player_a));
+        let turn = model.turn;
+        for action in agents[index].act(&view) {
+            if let PlayerAction::EndTurn = action {
+                break;
+            }
+            if model.handle_player_action(action.clone()) && !simultaneous {
+                counts[index].add(&action);
+            }
+        }
+        model.handle_player_action(PlayerAction::EndTurn);
+        if simultaneous && model.turn != turn {
+            for (player_a, action) in &model.resolved {
+                counts[if *player_a { 0 } else { 1 }].add(action);
+            }
+        }
+    }
+    (Outcome::Draw, model.turn)
+}
+
+impl ActionCounts {
+    pub fn add(&mut self, action: &PlayerAction) {
+        let kind = match action {
+            PlayerAction::ActivateShape(_) => "activate_shape",
+            PlayerAction::DeactivateShape(_) => "deactivate_shape",
+            PlayerAction::AttachShape { .. } => "attach_shape",
+            PlayerAction::MergeShapes { .. } => "merge_shapes",
+            PlayerAction::DetachCell { .. } => "detach_cell",
+            PlayerAction::UpgradePlant { .. } => "upgrade_plant",
+            PlayerAction::Attack { .. } => "attack",
+            PlayerAction::MoveShape { .. } => "move_shape",
+            PlayerAction::EndTurn => "end_turn",
+        };
+        *self.0.entry(kind.to_owned()).or_default() += 1;
+    }
+}
@@ -0,0 +1,218 @@
+//! Recognition of clicks, drags and long presses from synthetic input events
+
+use geng::prelude::*;
+
+use rusty_jam_2::game::gesture::*;
+use rusty_jam_2::game::input::{Binding, Bindings, InputAction};
+
+fn mouse_down(x: f64, y: f64) -> geng::Event {
+    geng::Event::MouseDown {
+        position: vec2(x, y),
+        button: geng::MouseButton::Left,
+    }
+}
+
+fn mouse_up(x: f64, y: f64) -> geng::Event {
+    geng::Event::MouseUp {
+        position: vec2(x, y),
+        button: geng::MouseButton::Left,
+    }
+}
+
+fn mouse_move(x: f64, y: f64) -> geng::Event {
+    geng::Event::MouseMove {
+        position: vec2(x, y),
+        delta: Vec2::ZERO,
+    }
+}
+
+fn touches(x: f64, y: f64) -> Vec<geng::TouchPoint> {
+    vec![geng::TouchPoint {
+        position: vec2(x, y),
+    }]
+}
+
+/// Feeds the events to a new recognizer, waiting `delay` seconds before each of them
+fn recognize(events: &[(f64, geng::Event)]) -> Vec<Gesture> {
+    recognize_with(&Bindings::default(), events)
+}
+
+fn recognize_with(bindings: &Bindings, events: &[(f64, geng::Event)]) -> Vec<Gesture> {
+    let mut recognizer = GestureRecognizer::new();
+    let mut gestures = Vec::new();
+    for (delay, event) in events {
+        gestures.extend(recognizer.update(*delay));
+        gestures.extend(recognizer.handle_event(bindings, event));
+    }
+    gestures
+}
+
+#[test]
+fn click_allows_jitter() {
+    let gestures = recognize(&[
+        (0.0, mouse_down(100.0, 100.0)),
+        (0.02, mouse_move(101.0, 99.5)),
+        (0.02, mouse_move(102.0, 100.5)),
+        (0.05, mouse_up(102.0, 100.5)),
+    ]);
+    assert_eq!(
+        gestures,
+        vec![
+            Gesture::Press(vec2(100.0, 100.0)),
+            Gesture::Click(vec2(100.0, 100.0)),
+        ]
+    );
+}
+
+#[test]
+fn drag_starts_after_threshold() {
+    let gestures = recognize(&[
+        (0.0, mouse_down(100.0, 100.0)),
+        (0.02, mouse_move(100.0, 100.0 + CLICK_DISTANCE)),
+        (0.02, mouse_move(120.0, 100.0)),
+        (0.02, mouse_move(140.0, 100.0)),
+        (0.02, mouse_up(140.0, 100.0)),
+    ]);
+    assert_eq!(
+        gestures,
+        vec![
+            Gesture::Press(vec2(100.0, 100.0)),
+            Gesture::DragStart(vec2(120.0, 100.0)),
+            Gesture::Drag(vec2(140.0, 100.0)),
+            Gesture::Drop(vec2(140.0, 100.0)),
+        ]
+    );
+}
+
+#[test]
+fn drag_back_to_start_is_not_a_click() {
+    let gestures = recognize(&[
+        (0.0, mouse_down(100.0, 100.0)),
+        (0.02, mouse_move(150.0, 100.0)),
+        (0.02, mouse_move(100.0, 100.0)),
+        (0.02, mouse_up(100.0, 100.0)),
+    ]);
+    assert_eq!(gestures.last(), Some(&Gesture::Drop(vec2(100.0, 100.0))));
+}
+
+#[test]
+fn slow_press_is_not_a_click() {
+    let gestures = recognize(&[
+        (0.0, mouse_down(100.0, 100.0)),
+        ((CLICK_TIME + LONG_PRESS_TIME) / 2.0, mouse_up(100.0, 100.0)),
+    ]);
+    assert_eq!(
+        gestures,
+        vec![
+            Gesture::Press(vec2(100.0, 100.0)),
+            Gesture::Release(vec2(100.0, 100.0)),
+        ]
+    );
+}
+
+#[test]
+fn holding_in_place_is_a_long_press() {
+    let gestures = recognize(&[
+        (0.0, mouse_down(100.0, 100.0)),
+        (0.1, mouse_move(101.0, 100.0)),
+        (LONG_PRESS_TIME, mouse_move(150.0, 100.0)),
+        (0.1, mouse_up(150.0, 100.0)),
+    ]);
+    assert_eq!(
+        gestures,
+        vec![
+            Gesture::Press(vec2(100.0, 100.0)),
+            Gesture::LongPress(vec2(100.0, 100.0)),
+            Gesture::Release(vec2(150.0, 100.0)),
+        ]
+    );
+}
+
+#[test]
+fn dragging_prevents_long_press() {
+    let gestures = recognize(&[
+        (0.0, mouse_down(100.0, 100.0)),
+        (0.1, mouse_move(150.0, 100.0)),
+        (LONG_PRESS_TIME, mouse_up(150.0, 100.0)),
+    ]);
+    assert!(!gestures
+        .iter()
+        .any(|gesture| matches!(gesture, Gesture::LongPress(_))));
+    assert_eq!(gestures.last(), Some(&Gesture::Drop(vec2(150.0, 100.0))));
+}
+
+#[test]
+fn touch_allows_more_jitter() {
+    let jitter = (CLICK_DISTANCE + TOUCH_CLICK_DISTANCE) / 2.0;
+    let gestures = recognize(&[
+        (
+            0.0,
+            geng::Event::TouchStart {
+                touches: touches(100.0, 100.0),
+            },
+        ),
+        (
+            0.05,
+            geng::Event::TouchMove {
+                touches: touches(100.0 + jitter, 100.0),
+            },
+        ),
+        (
+            0.05,
+            geng::Event::TouchEnd {
+                touches: touches(100.0 + jitter, 100.0),
+            },
+        ),
+    ]);
+    assert_eq!(
+        gestures,
+        vec![
+            Gesture::Press(vec2(100.0, 100.0)),
+            Gesture::Click(vec2(100.0, 100.0)),
+        ]
+    );
+}
+
+#[test]
+fn release_without_press_is_ignored() {
+    let gestures = recognize(&[
+        (0.0, mouse_move(100.0, 100.0)),
+        (0.1, mouse_up(100.0, 100.0)),
+        (LONG_PRESS_TIME, mouse_move(200.0, 100.0)),
+    ]);
+    assert_eq!(gestures, vec![]);
+}
+
+#[test]
+fn select_bound_to_key_uses_cursor() {
+    let mut bindings = Bindings::default();
+    bindings
+        .0
+        .insert(InputAction::Select, vec![Binding::Key(geng::Key::Space)]);
+    let gestures = recognize_with(
+        &bindings,
+        &[
+            (0.0, mouse_move(100.0, 100.0)),
+            (
+                0.0,
+                geng::Event::KeyDown {
+                    key: geng::Key::Space,
+                },
+            ),
+            (
+                0.05,
+                geng::Event::KeyUp {
+                    key: geng::Key::Space,
+                },
+            ),
+            (0.0, mouse_down(200.0, 200.0)),
+        ],
+    );
+    assert_eq!(
+        gestures,
+        vec![
+            Gesture::Press(vec2(100.0, 100.0)),
+            Gesture::Click(vec2(100.0, 100.0)),
+        ]
+    );
+}
@@ -0,0 +1,76 @@
+//! Arrangement of the shapes inside of their zones
+
+use geng::prelude::*;
+
+use rusty_jam_2::game::model::TriPos;
+use rusty_jam_2::game::render::packing::*;
+
+fn triangle() -> AABB<f32> {
+    shape_bounds(&[TriPos { x: 0, y: 0 }], 1.0)
+}
+
+fn hexagon() -> AABB<f32> {
+    let cells = [(0, 0), (1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0)].map(|(x, y)| TriPos { x, y });
+    shape_bounds(&cells, 1.0)
+}
+
+fn assert_inside(area: AABB<f32>, items: &[Item], positions: &[(u64, Vec2<f32>)]) {
+    const EPSILON: f32 = 1e-4;
+    for &(id, pos) in positions {
+        let item = items.iter().find(|item| item.id == id).unwrap();
+        let bounds = item.bounds.translate(pos);
+        assert!(
+            bounds.x_min >= area.x_min - EPSILON
+                && bounds.x_max <= area.x_max + EPSILON
+                && bounds.y_min >= area.y_min - EPSILON
+                && bounds.y_max <= area.y_max + EPSILON,
+            "shape {id} at {bounds:?} is outside of {area:?}"
+        );
+    }
+}
+
+#[test]
+fn packed_shapes_do_not_overlap() {
+    let area = AABB::point(vec2(0.0, 0.0)).extend_positive(vec2(10.0, 10.0));
+    let items: Vec<Item> = (0..6)
+        .map(|id| Item {
+            id,
+            bounds: hexagon(),
+            fixed: None,
+        })
+        .collect();
+    let positions = pack(area, &items);
+    assert_eq!(positions.len(), items.len());
+    assert_inside(area, &items, &positions);
+    let boxes: Vec<AABB<f32>> = positions
+        .iter()
+        .map(|&(id, pos)| items[id as usize].bounds.translate(pos))
+        .collect();
+    for (index, a) in boxes.iter().enumerate() {
+        for b in &boxes[index + 1..] {
+            let overlap =
+                a.x_min < b.x_max && b.x_min < a.x_max && a.y_min < b.y_max && b.y_min < a.y_max;
+            assert!(!overlap, "{a:?} overlaps {b:?}");
+        }
+    }
+}
+
+#[test]
+fn overflowing_shapes_stay_inside_the_area() {
+    let area = AABB::point(vec2(-2.0, -1.0)).extend_positive(vec2(4.0, 3.0));
+    let mut items: Vec<Item> = (0..40)
+        .map(|id| Item {
+            id,
+            bounds: if id % 3 == 0 { hexagon() } else { triangle() },
+            fixed: None,
+        })
+        .collect();
+    items.push(Item {
+        id: 40,
+        bounds: hexagon(),
+        fixed: Some(vec2(0.0, 0.0)),
+    });
+    let positions = pack(area, &items);
+    assert_eq!(positions.len(), 40);
+    assert_inside(area, &items, &positions);
+}
@@ -0,0 +1,167 @@
+//! Golden image tests of the renderer.
+//!
+//! Fixed model states are drawn into an offscreen framebuffer
+//! and compared against the images in `tests/golden`. No GPU is needed,
+//! a software OpenGL implementation is enough:
+//!
+//! ```sh
+//! LIBGL_ALWAYS_SOFTWARE=1 xvfb-run cargo test --test render
+//! ```
+//!
+//! A missing golden image is a failure. Set `UPDATE_GOLDEN=1` to write the images
+//! from the current output after an intended change, and commit them.
+
+use geng::prelude::*;
+
+use rusty_jam_2::game::model::*;
+use rusty_jam_2::game::render::Render;
+use rusty_jam_2::Assets;
+
+const WIDTH: usize = 640;
+const HEIGHT: usize = 360;
+/// Maximum difference of a color channel for pixels to be considered equal
+const CHANNEL_TOLERANCE: u8 = 2;
+/// Fraction of pixels that are allowed to differ, to allow for rasterization differences
+const PIXEL_TOLERANCE: f64 = 0.001;
+
+struct Scene {
+    name: &'static str,
+    view: PlayerView,
+}
+
+fn scenes() -> Vec<Scene> {
+    let initial = Model::new();
+
+    let mut developed = Model::with_mode(TurnMode::Alternating, Opponent::Human);
+    for _ in 0..6 {
+        developed.handle_player_action(PlayerAction::EndTurn);
+    }
+    for player_a in [true, false] {
+        let ids: Vec<Id> = developed
+            .player(player_a)
+            .shape_buffer
+            .0
+            .iter()
+            .map(|shape| shape.id)
+            .collect();
+        for id in ids {
+            developed.handle_player_action(PlayerAction::ActivateShape(id));
+        }
+        developed.handle_player_action(PlayerAction::EndTurn);
+    }
+
+    vec![
+        Scene {
+            name: "initial",
+            view: initial.view(Some(true)),
+        },
+        Scene {
+            name: "developed",
+            view: developed.view(Some(true)),
+        },
+        Scene {
+            name: "developed_spectator",
+            view: developed.view(None),
+        },
+    ]
+}
+
+fn main() {
+    let geng = Geng::new_with(geng::ContextOptions {
+        title: "Render tests".to_owned(),
+        ..Default::default()
+    });
+    let assets =
+        futures::executor::block_on(<Assets as geng::LoadAsset>::load(&geng, &static_path()))
+            .expect("Failed to load assets");
+    let assets = Rc::new(assets);
+    let update = std::env::var("UPDATE_GOLDEN").is_ok();
+    let golden_dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("golden");
+
+    let mut failures = Vec::new();
+    for scene in scenes() {
+        let actual = draw(&geng, &assets, &scene.view);
+        let path = golden_dir.join(format!("{}.png", scene.name));
+        if update {
+            actual.save(&path).expect("Failed to save golden image");
+            println!("{}: golden image written", scene.name);
+            continue;
+        }
+        if !path.exists() {
+            println!(
+                "{}: FAILED: missing golden image, run with UPDATE_GOLDEN=1 to create it",
+                scene.name
+            );
+            failures.push(scene.name);
+            continue;
+        }
+        let expected = image::open(&path)
+            .expect("Failed to load golden image")
+            .to_rgba8();
+        match compare(&expected, &actual) {
+            Ok(()) => println!("{}: ok", scene.name),
+            Err(error) => {
+                let actual_path = golden_dir.join(format!("{}.actual.png", scene.name));
+                actual.save(&actual_path).expect("Failed to save image");
+                println!("{}: FAILED: {error}", scene.name);
+                failures.push(scene.name);
+            }
+        }
+    }
+
+    if !failures.is_empty() {
+        eprintln!("Render tests failed: {}", failures.join(", "));
+        std::process::exit(1);
+    }
+}
+
+/// Draws the view into an offscreen framebuffer and reads back the pixels
+fn draw(geng: &Geng, assets: &Rc<Assets>, view: &PlayerView) -> image::RgbaImage {
+    let mut render = Render::new(geng, assets);
+    let mut texture = ugli::Texture::new_uninitialized(geng.ugli(), vec2(WIDTH, HEIGHT));
+    let mut framebuffer =
+        ugli::Framebuffer::new_color(geng.ugli(), ugli::ColorAttachment::Texture(&mut texture));
+    ugli::clear(&mut framebuffer, Some(Color::BLACK), None);
+    render.draw(view, &mut framebuffer);
+
+    let data = framebuffer.read_color();
+    let mut image = image::RgbaImage::new(WIDTH as u32, HEIGHT as u32);
+    for y in 0..HEIGHT {
+        for x in 0..WIDTH {
+            let color = data.get(x, y);
+            // The framebuffer origin is in the bottom left corner
+            image.put_pixel(
+                x as u32,
+                (HEIGHT - 1 - y) as u32,
+                image::Rgba([color.r, color.g, color.b, color.a]),
+            );
+        }
+    }
+    image
+}
+
+fn compare(expected: &image::RgbaImage, actual: &image::RgbaImage) -> Result<(), String> {
+    if expected.dimensions() != actual.dimensions() {
+        return Err(format!(
+            "size mismatch: expected {:?}, got {:?}",
+            expected.dimensions(),
+            actual.dimensions()
+        ));
+    }
+    let different = expected
+        .pixels()
+        .zip(actual.pixels())
+        .filter(|(a, b)| {
+            a.0.iter()
+                .zip(b.0.iter())
+                .any(|(a, b)| a.abs_diff(*b) > CHANNEL_TOLERANCE)
+        })
+        .count();
+    let total = expected.pixels().len();
+    if different as f64 > total as f64 * PIXEL_TOLERANCE {
+        return Err(format!("{different} of {total} pixels differ"));
+    }
+    Ok(())
+}
@@ -0,0 +1,445 @@
+//! Randomized tests of the rules engine: random action sequences must never
+//! panic or break the invariants of the model
+
+use geng::prelude::*;
+use std::collections::HashSet;
+
+use rusty_jam_2::game::model::logic::agents;
+use rusty_jam_2::game::model::*;
+use rusty_jam_2::simulation::{agent_seed, play_match, ActionCounts};
+
+const SEEDS: u64 = 200;
+const ACTIONS_PER_GAME: usize = 300;
+
+const MODES: [(TurnMode, Opponent); 4] = [
+    (TurnMode::Alternating, Opponent::Ai),
+    (TurnMode::Alternating, Opponent::Human),
+    (TurnMode::Simultaneous, Opponent::Ai),
+    (TurnMode::Simultaneous, Opponent::Human),
+];
+
+fn players(model: &Model) -> [&Player; 2] {
+    [&model.player_a, &model.player_b]
+}
+
+/// Every id in the model together with the cells of its shape
+fn all_shapes(model: &Model) -> Vec<(Id, &Shape)> {
+    players(model)
+        .into_iter()
+        .flat_map(|player| {
+            player
+                .shape_buffer
+                .0
+                .iter()
+                .chain(&player.active_shapes.0)
+                .map(|shape| (shape.id, &shape.shape))
+                .chain(
+                    player
+                        .shape_farm
+                        .plants
+                        .iter()
+                        .map(|plant| (plant.id, &plant.shape)),
+                )
+        })
+        .collect()
+}
+
+fn random_pos(rng: &mut StdRng) -> TriPos {
+    TriPos {
+        x: rng.gen_range(-3..=3),
+        y: rng.gen_range(-3..=3),
+    }
+}
+
+/// Generates an action that refers to existing ids most of the time
+fn random_action(model: &Model, rng: &mut StdRng) -> PlayerAction {
+    let ids: Vec<Id> = all_shapes(model).into_iter().map(|(id, _)| id).collect();
+    let mut id = || {
+        if rng.gen_bool(0.9) {
+            ids.choose(&mut *rng).copied().unwrap_or(0)
+        } else {
+            rng.gen_range(0..1000)
+        }
+    };
+    let (a, b) = (id(), id());
+    match rng.gen_range(0..10) {
+        0 => PlayerAction::ActivateShape(a),
+        1 => PlayerAction::DeactivateShape(a),
+        2 => PlayerAction::AttachShape {
+            triangle: a,
+            target: b,
+            pos: random_pos(rng),
+        },
+        3 => PlayerAction::MergeShapes {
+            source: a,
+            target: b,
+            rotation: rng.gen_range(-6..12),
+            offset: random_pos(rng),
+        },
+        4 => PlayerAction::DetachCell {
+            shape: a,
+            pos: random_pos(rng),
+        },
+        5 => PlayerAction::UpgradePlant {
+            source_shape: a,
+            target_plant: b,
+        },
+        6 => PlayerAction::Attack {
+            weapon: a,
+            target: b,
+        },
+        7 => PlayerAction::MoveShape {
+            shape: a,
+            pos: BoardPos {
+                depth: rng.gen_range(-1..=BOARD_DEPTH),
+                row: rng.gen_range(-1..=BOARD_ROWS),
+            },
+        },
+        _ => PlayerAction::EndTurn,
+    }
+}
+
+/// Tracks the ids seen so far to check that new ids are always increasing
+#[derive(Default)]
+struct IdHistory {
+    seen: HashSet<Id>,
+    max: Option<Id>,
+}
+
+impl IdHistory {
+    fn check(&mut self, model: &Model) {
+        let shapes = all_shapes(model);
+
+        let mut ids = HashSet::new();
+        for (id, shape) in &shapes {
+            assert!(ids.insert(*id), "Duplicate id {id}");
+            assert!(!shape.0.is_empty(), "Shape {id} is empty");
+        }
+
+        let new_ids: Vec<Id> = ids
+            .iter()
+            .copied()
+            .filter(|id| !self.seen.contains(id))
+            .collect();
+        for &id in &new_ids {
+            if let Some(max) = self.max {
+                assert!(id > max, "Id {id} was generated after {max}");
+            }
+        }
+        self.seen.extend(new_ids);
+        self.max = self.seen.iter().copied().max();
+    }
+}
+
+fn check_board(model: &Model) {
+    for player in players(model) {
+        let mut cells = HashSet::new();
+        for shape in &player.active_shapes.0 {
+            let pos = shape.board_pos.expect("Active shape is not on the board");
+            assert!(pos.is_valid(), "Shape {} is outside the board", shape.id);
+            assert!(
+                cells.insert((pos.depth, pos.row)),
+                "Two shapes share the cell {pos:?}"
+            );
+        }
+        for shape in &player.shape_buffer.0 {
+            assert!(shape.board_pos.is_none());
+        }
+    }
+}
+
+#[test]
+fn random_actions_keep_invariants() {
+    for (turn_mode, opponent) in MODES {
+        for seed in 0..SEEDS {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut model = Model::with_mode(turn_mode, opponent);
+            let mut history = IdHistory::default();
+            history.check(&model);
+            for _ in 0..ACTIONS_PER_GAME {
+                let action = random_action(&model, &mut rng);
+                model.handle_player_action(action);
+                history.check(&model);
+                check_board(&model);
+            }
+        }
+    }
+}
+
+#[test]
+fn agents_keep_invariants() {
+    for seed in 0..SEEDS / 10 {
+        for name_a in agents::AGENT_NAMES {
+            for name_b in agents::AGENT_NAMES {
+                let mut agent_a = agents::agent_by_name(name_a, agent_seed(seed, true)).unwrap();
+                let mut agent_b = agents::agent_by_name(name_b, agent_seed(seed, false)).unwrap();
+                play_match(
+                    &Rules::default(),
+                    100,
+                    [agent_a.as_mut(), agent_b.as_mut()],
+                    [&mut ActionCounts::default(), &mut ActionCounts::default()],
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn rotations_preserve_the_grid() {
+    let mut rng = StdRng::seed_from_u64(0);
+    for _ in 0..1000 {
+        let pos = random_pos(&mut rng);
+        let steps = rng.gen_range(0..6);
+        let rotated = pos.rotate(steps);
+        assert_eq!(rotated.rotate(6 - steps), pos);
+        // Rotations by 120 degrees keep the orientation of the triangle
+        assert_eq!(
+            pos.rotate(2).is_upside_down(),
+            pos.is_upside_down(),
+            "{pos:?}"
+        );
+    }
+}
+
+#[test]
+fn detaching_keeps_shapes_connected() {
+    let line = Shape(
+        [(0, 0), (1, 0), (2, 0)]
+            .map(|(x, y)| TriPos { x, y })
+            .to_vec(),
+    );
+    let (mut model, id) = model_with_active(line);
+    let max_id = |model: &Model| all_shapes(model).iter().map(|(id, _)| *id).max().unwrap();
+    let last_id = max_id(&model);
+
+    // Taking out the middle cell would split the shape
+    assert!(!model.handle_player_action(PlayerAction::DetachCell {
+        shape: id,
+        pos: TriPos { x: 1, y: 0 },
+    }));
+    assert_eq!(
+        model
+            .player_a
+            .active_shapes
+            .0
+            .get(&id)
+            .unwrap()
+            .shape
+            .0
+            .len(),
+        3
+    );
+    assert!(model.player_a.shape_buffer.0.is_empty());
+
+    assert!(model.handle_player_action(PlayerAction::DetachCell {
+        shape: id,
+        pos: TriPos { x: 2, y: 0 },
+    }));
+    let shape = &model.player_a.active_shapes.0.get(&id).unwrap().shape;
+    assert_eq!(shape.0.len(), 2);
+    assert!(shape.is_connected());
+    let buffer: Vec<&AliveShape> = model.player_a.shape_buffer.0.iter().collect();
+    assert_eq!(buffer.len(), 1);
+    assert_eq!(buffer[0].shape.0.len(), 1);
+    // Only the successful detach used up an id
+    assert_eq!(buffer[0].id, last_id + 1);
+    assert_eq!(max_id(&model), last_id + 1);
+}
+
+#[test]
+fn attack_preview_matches_outcome() {
+    let shape_size = |model: &Model, player_a: bool, id: Id| {
+        let player = model.player(player_a);
+        player
+            .active_shapes
+            .0
+            .get(&id)
+            .map(|shape| shape.shape.0.len())
+            .or_else(|| player.shape_farm.plants.get(&id).map(|_| 0))
+    };
+    let mut previews = 0;
+    for seed in 0..SEEDS {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut model = Model::with_mode(TurnMode::Alternating, Opponent::Human);
+        for _ in 0..ACTIONS_PER_GAME {
+            let action = random_action(&model, &mut rng);
+            if let PlayerAction::Attack { weapon, target } = action {
+                let player_a = model.player_a_turn;
+                if let Some(preview) = model.preview_attack(player_a, weapon, target) {
+                    previews += 1;
+                    let weapon_size = shape_size(&model, player_a, weapon).unwrap();
+                    let target_size = shape_size(&model, !player_a, target).unwrap();
+                    let time_left = model
+                        .player(!player_a)
+                        .shape_farm
+                        .plants
+                        .get(&target)
+                        .map(|plant| plant.time_left);
+                    model.handle_player_action(action);
+                    let weapon_after = shape_size(&model, player_a, weapon);
+                    let target_after = shape_size(&model, !player_a, target);
+                    assert_eq!(weapon_after.is_some(), preview.weapon_survives);
+                    assert_eq!(target_after.is_some(), preview.target_survives);
+                    if let Some(size) = weapon_after {
+                        assert_eq!(weapon_size - size, preview.weapon_loss);
+                    }
+                    if let Some(size) = target_after {
+                        assert_eq!(target_size - size, preview.target_loss);
+                    }
+                    if let (Some(before), Some(plant)) = (
+                        time_left,
+                        model.player(!player_a).shape_farm.plants.get(&target),
+                    ) {
+                        assert_eq!(plant.time_left - before, preview.plant_delay);
+                    }
+                    continue;
+                }
+            }
+            model.handle_player_action(action);
+        }
+    }
+    assert!(previews > 0, "No attacks were previewed");
+}
+
+#[test]
+fn simultaneous_attacks_resolve_together() {
+    let line = |length| Shape((0..length).map(|x| TriPos { x, y: 0 }).collect());
+    let setup = |length| PlayerSetup {
+        plants: vec![PlantSetup {
+            shape: line(1),
+            cooldown: 5,
+            time_left: 5,
+        }],
+        buffer: vec![],
+        active: vec![ActiveSetup {
+            shape: line(length),
+            pos: BoardPos { depth: 0, row: 1 },
+        }],
+    };
+    let scenario = Scenario {
+        name: String::new(),
+        description: String::new(),
+        player_a: setup(3),
+        player_b: setup(2),
+        player_a_turn: true,
+        goal: Goal::Survive { turns: 1 },
+    };
+    let mut model = Model::from_scenario(&scenario, Opponent::External);
+    model.turn_mode = TurnMode::Simultaneous;
+    let weapon_a = model.player_a.active_shapes.0.iter().next().unwrap().id;
+    let weapon_b = model.player_b.active_shapes.0.iter().next().unwrap().id;
+
+    // Player A's attack alone would destroy B's weapon before it strikes back
+    model.submit_actions(
+        true,
+        vec![PlayerAction::Attack {
+            weapon: weapon_a,
+            target: weapon_b,
+        }],
+    );
+    model.submit_actions(
+        false,
+        vec![PlayerAction::Attack {
+            weapon: weapon_b,
+            target: weapon_a,
+        }],
+    );
+    assert!(model.player_a.active_shapes.0.get(&weapon_a).is_none());
+    assert!(model.player_b.active_shapes.0.get(&weapon_b).is_none());
+}
+
+#[test]
+fn newer_plans_replace_older_ones() {
+    let mut model = Model::with_mode(TurnMode::Simultaneous, Opponent::Human);
+    let (shape, other): (Id, Id) = (1, 2);
+    for action in [
+        PlayerAction::ActivateShape(shape),
+        PlayerAction::MoveShape {
+            shape,
+            pos: BoardPos { depth: 0, row: 0 },
+        },
+        PlayerAction::ActivateShape(shape),
+        PlayerAction::ActivateShape(other),
+        PlayerAction::MoveShape {
+            shape,
+            pos: BoardPos { depth: 1, row: 0 },
+        },
+    ] {
+        model.handle_player_action(action);
+    }
+    assert_eq!(model.planned.len(), 3);
+    assert!(matches!(
+        model.planned.last(),
+        Some(PlayerAction::MoveShape {
+            pos: BoardPos { depth: 1, .. },
+            ..
+        })
+    ));
+}
+
+/// Whether the shape stays connected while damage removes its cells from the end
+fn connected_when_damaged(shape: &Shape) -> bool {
+    (1..=shape.0.len()).all(|len| Shape(shape.0[..len].to_vec()).is_connected())
+}
+
+#[test]
+fn merged_shapes_stay_connected_when_damaged() {
+    let target = Shape(vec![TriPos { x: 0, y: 0 }]);
+    // Only the second cell of the source touches the target
+    let source = Shape(vec![TriPos { x: 2, y: 0 }, TriPos { x: 1, y: 0 }]);
+    let merged = target
+        .merge(&source, 0, TriPos { x: 0, y: 0 })
+        .expect("the shapes share an edge");
+    assert_eq!(merged.0.len(), 3);
+    assert!(connected_when_damaged(&merged), "{merged:?}");
+}
+
+fn hexagon() -> Shape {
+    Shape(
+        [(0, 0), (1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0)]
+            .map(|(x, y)| TriPos { x, y })
+            .to_vec(),
+    )
+}
+
+/// A hotseat match where player A has the given shape on the board
+fn model_with_active(shape: Shape) -> (Model, Id) {
+    let plant = PlantSetup {
+        shape: Shape(vec![TriPos { x: 0, y: 0 }]),
+        cooldown: 2,
+        time_left: 2,
+    };
+    let scenario = Scenario {
+        name: "test".to_owned(),
+        description: String::new(),
+        player_a: PlayerSetup {
+            plants: vec![plant.clone()],
+            buffer: vec![],
+            active: vec![ActiveSetup {
+                shape,
+                pos: BoardPos { depth: 0, row: 0 },
+            }],
+        },
+        player_b: PlayerSetup {
+            plants: vec![plant],
+            ..Default::default()
+        },
+        player_a_turn: true,
+        goal: Goal::Survive { turns: 1 },
+    };
+    let model = Model::from_scenario(&scenario, Opponent::Human);
+    let id = model.player_a.active_shapes.0.iter().next().unwrap().id;
+    (model, id)
+}
+
+#[test]
+fn detached_shapes_stay_connected_when_damaged() {
+    let (mut model, id) = model_with_active(hexagon());
+    assert!(model.handle_player_action(PlayerAction::DetachCell {
+        shape: id,
+        pos: TriPos { x: 1, y: 0 },
+    }));
+    let shape = &model.player_a.active_shapes.0.get(&id).unwrap().shape;
+    assert_eq!(shape.0.len(), 5);
+    assert!(connected_when_damaged(shape), "{shape:?}");
+}